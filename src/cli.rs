@@ -1,14 +1,14 @@
 use anyhow::{Context, Ok, Result};
 use clap::Command;
 use serde::{Deserialize, Serialize};
-use std::io::prelude::*;
-use url::Url;
+use std::path::Path;
 
-use crate::{parser, template};
+use crate::generator::Target;
+use crate::{ir, loader, options::Options, parser, resolver};
 
 pub fn create_cli() -> Command {
     Command::new("api-gen")
-        .about("Generate typescript code from OpenAPI spec")
+        .about("Generate API clients from OpenAPI spec")
         .subcommand_required(true)
         .arg_required_else_help(true)
         .allow_external_subcommands(true)
@@ -24,22 +24,18 @@ pub fn create_cli() -> Command {
 struct Config {
     source: String,
     path: String,
+    #[serde(default)]
+    target: Target,
+    #[serde(flatten)]
+    options: Options,
 }
 
 impl Config {
-    fn get_data(&self) -> Result<String> {
-        if Url::parse(&self.source).is_ok() {
-            let body = reqwest::blocking::get(&self.source)
-                .context("Failed to fetch data from URL")?
-                .text()
-                .context("Failed to read response body")?;
+    fn get_api(&self) -> Result<ir::Api> {
+        let url = loader::source_url(&self.source)?;
+        let document = resolver::resolve(loader::load(&url)?, &url)?;
 
-            return Ok(body);
-        };
-
-        let file_contents = std::fs::read_to_string(&self.source).context("Unable to read file")?;
-
-        Ok(file_contents)
+        parser::parse_schema(document, &url)
     }
 }
 
@@ -47,6 +43,8 @@ pub fn init() -> Result<()> {
     let config = Config {
         source: "__REPLACE__".to_string(),
         path: "lib/types.ts".to_string(),
+        target: Target::default(),
+        options: Options::default(),
     };
 
     let config_str = serde_json::to_string_pretty(&config).context("Failed to create file data")?;
@@ -60,26 +58,22 @@ pub fn generate() -> Result<()> {
 
     let config: Config = serde_json::from_str(&input_file).context("Unable to parse JSON")?;
 
-    let schema: parser::Schema =
-        serde_json::from_str(config.get_data()?.as_str()).context("Unable to parse JSON")?;
+    let api = config.get_api()?;
 
-    let folder_path = config
-        .path
-        .split("/")
-        .filter(|f| !f.ends_with(".ts"))
-        .collect::<Vec<_>>()
-        .join("/");
+    let files =
+        config
+            .target
+            .generator()
+            .generate(api, &config.options, Path::new(&config.path))?;
 
-    std::fs::create_dir_all(folder_path).context("Unable to create directory")?;
-    let mut output_file = std::fs::File::create(&config.path).context("Unable to create file")?;
+    for file in files {
+        if let Some(folder_path) = file.path.parent() {
+            std::fs::create_dir_all(folder_path).context("Unable to create directory")?;
+        }
 
-    template::generate_file_lines(schema)
-        .iter()
-        .for_each(|line| {
-            output_file
-                .write_all(line.as_bytes())
-                .expect("Unable to write data");
-        });
+        std::fs::write(&file.path, file.contents)
+            .with_context(|| format!("Unable to write {}", file.path.display()))?;
+    }
 
     Ok(())
 }
@@ -0,0 +1,235 @@
+use anyhow::Result;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::ir;
+use crate::options::Options;
+use crate::python;
+use crate::rust;
+use crate::template;
+use crate::zod;
+
+/// An output backend, turning the parsed API into source files.
+pub trait Generator {
+    /// Files to write. `path` is the `path` configured in `api-gen.json`.
+    fn generate(&self, api: ir::Api, options: &Options, path: &Path) -> Result<Vec<OutputFile>>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputFile {
+    pub path: PathBuf,
+    pub contents: String,
+}
+
+/// Backend selected with the `target` field of `api-gen.json`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Target {
+    /// Types and `fetch` based functions in a single file.
+    #[default]
+    TypeScript,
+    /// The TypeScript client with Zod schemas, types are inferred from them.
+    Zod,
+    /// A crate with serde models and a `reqwest` based client.
+    Rust,
+    /// A module with Pydantic models and `httpx` based clients.
+    Python,
+}
+
+impl Target {
+    pub fn generator(&self) -> Box<dyn Generator> {
+        match self {
+            Target::TypeScript => Box::new(template::TypeScript),
+            Target::Zod => Box::new(zod::Zod),
+            Target::Rust => Box::new(rust::Rust),
+            Target::Python => Box::new(python::Python),
+        }
+    }
+}
+
+/// Entries of `map` in spec order when `preserve_spec_order` is set, sorted
+/// by key otherwise.
+pub fn ordered<'a, V>(map: &'a IndexMap<String, V>, options: &Options) -> Vec<(&'a String, &'a V)> {
+    let mut entries: Vec<(&String, &V)> = map.iter().collect();
+
+    if !options.preserve_spec_order {
+        entries.sort_by_key(|(key, _)| *key);
+    }
+
+    entries
+}
+
+/// Properties in spec order when `preserve_spec_order` is set, sorted by
+/// name otherwise.
+pub fn ordered_properties<'a>(
+    properties: impl IntoIterator<Item = &'a ir::Property>,
+    options: &Options,
+) -> Vec<&'a ir::Property> {
+    let mut properties: Vec<&ir::Property> = properties.into_iter().collect();
+
+    if !options.preserve_spec_order {
+        properties.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    properties
+}
+
+/// Operations in spec order when `preserve_spec_order` is set, sorted by path
+/// otherwise.
+pub fn ordered_operations<'a>(
+    operations: &'a [ir::Operation],
+    options: &Options,
+) -> Vec<&'a ir::Operation> {
+    let mut operations: Vec<&ir::Operation> = operations.iter().collect();
+
+    // the sort is stable, operations of a path keep their method order
+    if !options.preserve_spec_order {
+        operations.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+
+    operations
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn generate_typescript_by_default() {
+        let target: Target = serde_json::from_str(r#""typescript""#).unwrap();
+        assert_eq!(target, Target::default());
+        assert!(serde_json::from_str::<Target>(r#""cobol""#).is_err());
+
+        let files = target
+            .generator()
+            .generate(
+                ir::Api::default(),
+                &Options::default(),
+                Path::new("lib/types.ts"),
+            )
+            .unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, Path::new("lib/types.ts"));
+        assert!(files[0].contents.contains("async function fetcher"));
+    }
+
+    #[test]
+    fn generate_zod_schemas() {
+        let api = ir::Api {
+            types: indexmap::IndexMap::from([
+                ("Tag".to_string(), ir::Type::String),
+                (
+                    "Node".to_string(),
+                    ir::Type::Array(Box::new(ir::Type::Ref("Node".to_string()))),
+                ),
+            ]),
+            ..Default::default()
+        };
+        let options = Options {
+            validate_responses: true,
+            ..Default::default()
+        };
+
+        let files = Target::Zod
+            .generator()
+            .generate(api, &options, Path::new("lib/types.ts"))
+            .unwrap();
+        let contents = &files[0].contents;
+
+        assert!(contents.starts_with("import { z } from \"zod\";"));
+        assert!(contents
+            .contains("export const Tag = z.string();\nexport type Tag = z.infer<typeof Tag>;\n"));
+        assert!(contents.contains(
+            "export type Node = Node[];\nexport const Node: z.ZodType<Node> = z.array(z.lazy(() => Node));\n"
+        ));
+        assert!(contents.contains("schema?: z.ZodType<TResult>,"));
+    }
+
+    #[test]
+    fn generate_rust_crate() {
+        let files = Target::Rust
+            .generator()
+            .generate(
+                ir::Api::default(),
+                &Options::default(),
+                Path::new("clients/pet_store"),
+            )
+            .unwrap();
+
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, Path::new("clients/pet_store/Cargo.toml"));
+        assert!(files[0].contents.contains("name = \"pet-store\""));
+        assert_eq!(files[1].path, Path::new("clients/pet_store/src/lib.rs"));
+        assert!(files[1].contents.contains("pub mod blocking {"));
+    }
+
+    #[test]
+    fn generate_python_module() {
+        let files = Target::Python
+            .generator()
+            .generate(
+                ir::Api::default(),
+                &Options::default(),
+                Path::new("clients/pet_store.py"),
+            )
+            .unwrap();
+
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, Path::new("clients/pet_store.py"));
+        assert!(files[0].contents.contains("class AsyncClient:"));
+    }
+
+    #[test]
+    fn generate_independently_of_spec_order() {
+        let pets = r##""/pets": { "get": { "responses": {
+            "200": { "description": "", "schema": { "type": "array", "items": { "$ref": "#/definitions/Pet" } } },
+            "404": { "description": "", "schema": { "$ref": "#/definitions/Error" } },
+            "default": { "description": "", "schema": { "$ref": "#/definitions/Error" } }
+        } } }"##;
+        let shuffled_pets = r##""/pets": { "get": { "responses": {
+            "default": { "description": "", "schema": { "$ref": "#/definitions/Error" } },
+            "404": { "description": "", "schema": { "$ref": "#/definitions/Error" } },
+            "200": { "description": "", "schema": { "type": "array", "items": { "$ref": "#/definitions/Pet" } } }
+        } } }"##;
+        let owners = r##""/owners/{id}": { "delete": {
+            "parameters": [{ "name": "id", "in": "path", "type": "integer", "required": true }],
+            "responses": { "204": { "description": "" }, "500": { "description": "", "schema": { "$ref": "#/definitions/Error" } } }
+        } }"##;
+        let definitions = r##""definitions": {
+            "Pet": { "type": "object", "properties": { "name": { "type": "string" }, "age": { "type": "integer" } } },
+            "Error": { "type": "object", "properties": { "message": { "type": "string" } } }
+        }"##;
+        let shuffled_definitions = r##""definitions": {
+            "Error": { "type": "object", "properties": { "message": { "type": "string" } } },
+            "Pet": { "type": "object", "properties": { "age": { "type": "integer" }, "name": { "type": "string" } } }
+        }"##;
+
+        let generate = |target: Target, paths: [&str; 2], definitions: &str| {
+            let spec = format!(
+                r#"{{ "swagger": "2.0", "host": "api.example.com", "paths": {{ {} }}, {definitions} }}"#,
+                paths.join(", ")
+            );
+            let source = url::Url::parse("https://api.example.com/openapi.json").unwrap();
+            let api =
+                crate::parser::parse_schema(serde_json::from_str(&spec).unwrap(), &source).unwrap();
+
+            target
+                .generator()
+                .generate(api, &Options::default(), Path::new("out"))
+                .unwrap()
+        };
+
+        for target in [
+            Target::TypeScript,
+            Target::Zod,
+            Target::Rust,
+            Target::Python,
+        ] {
+            assert_eq!(
+                generate(target, [pets, owners], definitions),
+                generate(target, [owners, shuffled_pets], shuffled_definitions)
+            );
+        }
+    }
+}
@@ -0,0 +1,666 @@
+use indexmap::IndexMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Spec-agnostic description of an API. `parser` lowers Swagger 2 and
+/// OpenAPI 3.x documents into it, the emitters only ever read this.
+#[derive(Debug, Clone, Default)]
+pub struct Api {
+    /// URL every operation path is relative to.
+    pub base_url: String,
+    /// Named types, keyed by their name in the spec, in spec order.
+    pub types: IndexMap<String, Type>,
+    /// Operations in spec order, grouped by path.
+    pub operations: Vec<Operation>,
+    /// Operations the API calls on its consumers, keyed by webhook name.
+    pub webhooks: IndexMap<String, Vec<Operation>>,
+}
+
+impl Api {
+    /// Renames named types (old name -> new name) and every reference to
+    /// them. Variant values keep using the old name.
+    pub fn rename_types(&mut self, renames: &HashMap<String, String>) {
+        if renames.is_empty() {
+            return;
+        }
+
+        self.types = std::mem::take(&mut self.types)
+            .into_iter()
+            .map(|(name, ty)| match renames.get(&name) {
+                Some(new) => (new.clone(), ty),
+                None => (name, ty),
+            })
+            .collect();
+
+        let mut rename = |ty: &mut Type| {
+            if let Type::Ref(name) = ty {
+                if let Some(new) = renames.get(name) {
+                    *name = new.clone();
+                }
+            }
+        };
+
+        let operations = self
+            .operations
+            .iter_mut()
+            .chain(self.webhooks.values_mut().flatten());
+
+        self.types
+            .values_mut()
+            .chain(operations.flat_map(Operation::types_mut))
+            .for_each(|ty| ty.walk_mut(&mut rename));
+    }
+
+    /// Names that have to stay apart, with where they are declared: the
+    /// properties of each object and the parameters of each operation in each
+    /// location.
+    pub fn sibling_names(&self) -> Vec<(String, Vec<String>)> {
+        fn properties(location: &str, ty: &Type, siblings: &mut Vec<(String, Vec<String>)>) {
+            ty.walk(&mut |ty| {
+                if let Type::Object(object) = ty {
+                    siblings.push((
+                        location.to_string(),
+                        object
+                            .properties
+                            .iter()
+                            .map(|prop| prop.name.clone())
+                            .collect(),
+                    ));
+                }
+            })
+        }
+
+        let mut siblings = Vec::new();
+
+        self.types
+            .iter()
+            .for_each(|(name, ty)| properties(&format!("schema {name}"), ty, &mut siblings));
+
+        let operations = self
+            .operations
+            .iter()
+            .chain(self.webhooks.values().flatten());
+
+        operations.for_each(|op| {
+            let location = format!("{} {}", op.method.as_str(), op.path);
+
+            op.types()
+                .for_each(|ty| properties(&location, ty, &mut siblings));
+
+            [
+                ParameterLocation::Query,
+                ParameterLocation::Path,
+                ParameterLocation::Header,
+                ParameterLocation::Cookie,
+            ]
+            .into_iter()
+            .for_each(|param_location| {
+                siblings.push((
+                    location.clone(),
+                    op.parameters_in(param_location)
+                        .map(|param| param.name.clone())
+                        .collect(),
+                ))
+            });
+        });
+
+        siblings
+    }
+
+    /// Named types used as variants of a discriminated union: type name ->
+    /// discriminator property -> values the type is tagged with.
+    pub fn variant_tags(&self) -> HashMap<String, BTreeMap<String, Vec<String>>> {
+        let mut tags: HashMap<String, BTreeMap<String, Vec<String>>> = HashMap::new();
+
+        let mut collect = |ty: &Type| {
+            if let (Some(_), Type::Union(members)) = (ty.discriminator(), ty) {
+                members.iter().for_each(|member| {
+                    if let Type::Variant {
+                        property,
+                        value,
+                        ty,
+                    } = member
+                    {
+                        if let Type::Ref(name) = ty.as_ref() {
+                            let values = tags
+                                .entry(name.clone())
+                                .or_default()
+                                .entry(property.clone())
+                                .or_default();
+                            if !values.contains(value) {
+                                values.push(value.clone());
+                            }
+                        }
+                    }
+                });
+            }
+        };
+
+        let operations = self
+            .operations
+            .iter()
+            .chain(self.webhooks.values().flatten());
+
+        self.types
+            .values()
+            .chain(operations.flat_map(Operation::types))
+            .for_each(|ty| ty.walk(&mut collect));
+
+        tags
+    }
+
+    /// Whether `ty` holds a file, directly or through the named types it
+    /// references.
+    pub fn contains_file(&self, ty: &Type) -> bool {
+        let mut seen = HashSet::new();
+        let mut stack = vec![ty];
+        let mut found = false;
+
+        while let Some(current) = stack.pop() {
+            current.walk(&mut |ty| match ty {
+                Type::File => found = true,
+                Type::Ref(name) if seen.insert(name.clone()) => {
+                    stack.extend(self.types.get(name));
+                }
+                _ => (),
+            });
+        }
+
+        found
+    }
+
+    /// Named types that reference themselves, directly or through other
+    /// named types.
+    pub fn recursive_types(&self) -> HashSet<String> {
+        let refs: HashMap<&String, Vec<String>> = self
+            .types
+            .iter()
+            .map(|(name, ty)| {
+                let mut refs = Vec::new();
+                ty.walk(&mut |ty| {
+                    if let Type::Ref(target) = ty {
+                        refs.push(target.clone());
+                    }
+                });
+                (name, refs)
+            })
+            .collect();
+
+        self.types
+            .keys()
+            .filter(|name| {
+                let mut seen = HashSet::new();
+                let mut stack: Vec<&String> = refs[name].iter().collect();
+
+                while let Some(current) = stack.pop() {
+                    if current == *name {
+                        return true;
+                    }
+                    if seen.insert(current) {
+                        stack.extend(refs.get(current).into_iter().flatten());
+                    }
+                }
+
+                false
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    Get,
+    Post,
+    Put,
+    Delete,
+    Patch,
+    Head,
+    Options,
+    Trace,
+}
+
+impl Method {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Delete => "DELETE",
+            Method::Patch => "PATCH",
+            Method::Head => "HEAD",
+            Method::Options => "OPTIONS",
+            Method::Trace => "TRACE",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Operation {
+    pub method: Method,
+    /// Path template, e.g. `/apps/{app_name}`. Webhooks use their name.
+    pub path: String,
+    pub operation_id: Option<String>,
+    pub description: String,
+    /// Query, path, header and cookie parameters, in spec order.
+    pub parameters: Vec<Parameter>,
+    pub body: Option<Body>,
+    /// Responses ordered by status code.
+    pub responses: Vec<Response>,
+}
+
+impl Operation {
+    pub fn parameters_in(&self, location: ParameterLocation) -> impl Iterator<Item = &Parameter> {
+        self.parameters
+            .iter()
+            .filter(move |param| param.location == location)
+    }
+
+    /// Responses whose status code starts with one of `classes`, e.g. `"2"`.
+    pub fn responses_in<'a>(&'a self, classes: &'a [&str]) -> impl Iterator<Item = &'a Response> {
+        self.responses
+            .iter()
+            .filter(|res| classes.iter().any(|class| res.status.starts_with(class)))
+    }
+
+    /// How successful responses are read, following the first one with a
+    /// payload.
+    pub fn response_encoding(&self) -> ResponseEncoding {
+        self.responses_in(&["2"])
+            .find(|res| res.ty.is_some())
+            .map(|res| res.encoding)
+            .unwrap_or_default()
+    }
+
+    /// Literal text and `{name}` placeholders of the path, in order. A `{`
+    /// without a closing `}` is literal text.
+    pub fn path_parts(&self) -> Vec<PathPart<'_>> {
+        let mut parts = Vec::new();
+        let mut rest = self.path.as_str();
+
+        while let Some((start, end)) = rest
+            .find('{')
+            .and_then(|start| Some((start, start + rest[start..].find('}')?)))
+        {
+            if start > 0 {
+                parts.push(PathPart::Literal(&rest[..start]));
+            }
+            parts.push(PathPart::Placeholder(&rest[start + 1..end]));
+            rest = &rest[end + 1..];
+        }
+
+        if !rest.is_empty() {
+            parts.push(PathPart::Literal(rest));
+        }
+
+        parts
+    }
+
+    fn types(&self) -> impl Iterator<Item = &Type> {
+        self.parameters
+            .iter()
+            .map(|param| &param.ty)
+            .chain(self.body.iter().map(|body| &body.ty))
+            .chain(self.responses.iter().filter_map(|res| res.ty.as_ref()))
+    }
+
+    fn types_mut(&mut self) -> impl Iterator<Item = &mut Type> {
+        self.parameters
+            .iter_mut()
+            .map(|param| &mut param.ty)
+            .chain(self.body.iter_mut().map(|body| &mut body.ty))
+            .chain(self.responses.iter_mut().filter_map(|res| res.ty.as_mut()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParameterLocation {
+    Query,
+    Path,
+    Header,
+    Cookie,
+}
+
+#[derive(Debug, Clone)]
+pub struct Parameter {
+    pub name: String,
+    pub location: ParameterLocation,
+    pub required: bool,
+    /// Whether `null` is accepted on top of `ty`.
+    pub nullable: bool,
+    pub ty: Type,
+    /// How array and object values are put in the URL, `None` for anything
+    /// else.
+    pub serialization: Option<Serialization>,
+}
+
+/// Serialization of array and object parameters, named after the Swagger 2
+/// `collectionFormat` values plus the OpenAPI 3 object styles.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Serialization {
+    Csv,
+    Ssv,
+    Tsv,
+    Pipes,
+    Multi,
+    Form,
+    DeepObject,
+}
+
+impl Serialization {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Serialization::Csv => "csv",
+            Serialization::Ssv => "ssv",
+            Serialization::Tsv => "tsv",
+            Serialization::Pipes => "pipes",
+            Serialization::Multi => "multi",
+            Serialization::Form => "form",
+            Serialization::DeepObject => "deepObject",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Body {
+    pub ty: Type,
+    pub encoding: BodyEncoding,
+}
+
+/// Request body encodings the generated clients know how to send.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum BodyEncoding {
+    #[default]
+    Json,
+    UrlEncoded,
+    Multipart,
+}
+
+/// Piece of an operation path, see [`Operation::path_parts`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathPart<'a> {
+    Literal(&'a str),
+    Placeholder(&'a str),
+}
+
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: String,
+    /// `None` when the spec doesn't describe the payload.
+    pub ty: Option<Type>,
+    pub encoding: ResponseEncoding,
+}
+
+/// How a response payload is read, derived from the media type the
+/// operation produces.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ResponseEncoding {
+    #[default]
+    Json,
+    /// `text/*` and XML payloads, read as a string.
+    Text,
+    /// Any other media type, read as raw bytes.
+    Binary,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    /// A schema without any constraint.
+    Any,
+    /// A schema no value matches.
+    Never,
+    Null,
+    Boolean,
+    Integer,
+    Number,
+    String,
+    /// Binary content, e.g. an uploaded file.
+    File,
+    /// The single value allowed by `const`.
+    Literal(serde_json::Value),
+    Enum(Vec<serde_json::Value>),
+    Array(Box<Type>),
+    /// Leading items with their own type, followed by any number of `rest`.
+    Tuple {
+        items: Vec<Type>,
+        rest: Option<Box<Type>>,
+    },
+    Object(ObjectType),
+    /// Reference to one of `Api::types`.
+    Ref(String),
+    Union(Vec<Type>),
+    Intersection(Vec<Type>),
+    /// Member of a discriminated union: `ty` with `property` set to `value`.
+    Variant {
+        property: String,
+        value: String,
+        ty: Box<Type>,
+    },
+    Nullable(Box<Type>),
+}
+
+impl Type {
+    /// Values matching every member. Members without any constraint, such as
+    /// a lone `description`, are dropped and a member that only adds
+    /// `nullable` makes the whole type nullable.
+    pub fn intersection(members: Vec<Type>) -> Type {
+        let mut nullable = false;
+        let mut members: Vec<Type> = members
+            .into_iter()
+            .filter(|member| match member {
+                Type::Any => false,
+                Type::Nullable(inner) if **inner == Type::Any => {
+                    nullable = true;
+                    false
+                }
+                _ => true,
+            })
+            .collect();
+
+        let ty = match members.len() {
+            0 => Type::Any,
+            1 => members.pop().unwrap(),
+            _ => Type::Intersection(members),
+        };
+
+        match nullable {
+            true => ty.nullable(),
+            false => ty,
+        }
+    }
+
+    /// Property telling the members of a union apart, when every member is a
+    /// variant on the same property.
+    pub fn discriminator(&self) -> Option<&str> {
+        let Type::Union(members) = self else {
+            return None;
+        };
+
+        let properties: Vec<&str> = members
+            .iter()
+            .map(|member| match member {
+                Type::Variant { property, .. } => Some(property.as_str()),
+                _ => None,
+            })
+            .collect::<Option<_>>()?;
+
+        match properties.split_first() {
+            Some((first, rest)) if rest.iter().all(|property| property == first) => Some(first),
+            _ => None,
+        }
+    }
+
+    /// Also accepts `null`.
+    pub fn nullable(self) -> Type {
+        match self {
+            Type::Null | Type::Nullable(_) => self,
+            other => Type::Nullable(Box::new(other)),
+        }
+    }
+
+    /// Calls `f` on the type and every type nested inside it.
+    pub fn walk(&self, f: &mut impl FnMut(&Type)) {
+        f(self);
+
+        match self {
+            Type::Array(inner) | Type::Nullable(inner) | Type::Variant { ty: inner, .. } => {
+                inner.walk(f)
+            }
+            Type::Tuple { items, rest } => items
+                .iter()
+                .chain(rest.as_deref())
+                .for_each(|ty| ty.walk(f)),
+            Type::Object(object) => object
+                .properties
+                .iter()
+                .map(|prop| &prop.ty)
+                .chain(object.additional.as_deref())
+                .for_each(|ty| ty.walk(f)),
+            Type::Union(members) | Type::Intersection(members) => {
+                members.iter().for_each(|ty| ty.walk(f))
+            }
+            _ => (),
+        }
+    }
+
+    pub fn walk_mut(&mut self, f: &mut impl FnMut(&mut Type)) {
+        f(self);
+
+        match self {
+            Type::Array(inner) | Type::Nullable(inner) | Type::Variant { ty: inner, .. } => {
+                inner.walk_mut(f)
+            }
+            Type::Tuple { items, rest } => items
+                .iter_mut()
+                .chain(rest.as_deref_mut())
+                .for_each(|ty| ty.walk_mut(f)),
+            Type::Object(object) => object
+                .properties
+                .iter_mut()
+                .map(|prop| &mut prop.ty)
+                .chain(object.additional.as_deref_mut())
+                .for_each(|ty| ty.walk_mut(f)),
+            Type::Union(members) | Type::Intersection(members) => {
+                members.iter_mut().for_each(|ty| ty.walk_mut(f))
+            }
+            _ => (),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ObjectType {
+    /// Declared properties, in spec order.
+    pub properties: Vec<Property>,
+    /// Type of the undeclared properties, `None` when the schema doesn't
+    /// describe them.
+    pub additional: Option<Box<Type>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Property {
+    /// Name on the wire.
+    pub name: String,
+    pub description: Option<String>,
+    pub required: bool,
+    pub ty: Type,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rename_types() {
+        let mut api = Api {
+            types: IndexMap::from([
+                ("foo_bar".to_string(), Type::String),
+                (
+                    "Pet".to_string(),
+                    Type::Union(vec![Type::Variant {
+                        property: "kind".to_string(),
+                        value: "Cat".to_string(),
+                        ty: Box::new(Type::Ref("Cat".to_string())),
+                    }]),
+                ),
+                (
+                    "Tags".to_string(),
+                    Type::Array(Box::new(Type::Ref("foo_bar".to_string()))),
+                ),
+                ("Cat".to_string(), Type::Ref("Pet_base".to_string())),
+            ]),
+            ..Default::default()
+        };
+
+        api.rename_types(&HashMap::from([
+            ("foo_bar".to_string(), "foo_bar_2".to_string()),
+            ("Cat".to_string(), "Cat_2".to_string()),
+        ]));
+
+        assert_eq!(
+            api.types.keys().collect::<Vec<_>>(),
+            vec!["foo_bar_2", "Pet", "Tags", "Cat_2"]
+        );
+        assert_eq!(
+            api.types["Pet"],
+            Type::Union(vec![Type::Variant {
+                property: "kind".to_string(),
+                value: "Cat".to_string(),
+                ty: Box::new(Type::Ref("Cat_2".to_string())),
+            }])
+        );
+        assert_eq!(
+            api.types["Tags"],
+            Type::Array(Box::new(Type::Ref("foo_bar_2".to_string())))
+        );
+    }
+
+    #[test]
+    fn find_recursive_types() {
+        let api = Api {
+            types: IndexMap::from([
+                (
+                    "Node".to_string(),
+                    Type::Array(Box::new(Type::Ref("Node".to_string()))),
+                ),
+                ("A".to_string(), Type::Ref("B".to_string())),
+                (
+                    "B".to_string(),
+                    Type::Union(vec![Type::Ref("A".to_string()), Type::Null]),
+                ),
+                ("C".to_string(), Type::Ref("A".to_string())),
+                ("D".to_string(), Type::Ref("Missing".to_string())),
+            ]),
+            ..Default::default()
+        };
+
+        let mut recursive: Vec<String> = api.recursive_types().into_iter().collect();
+        recursive.sort();
+
+        assert_eq!(recursive, vec!["A", "B", "Node"]);
+    }
+
+    #[test]
+    fn split_paths() {
+        let op = |path: &str| Operation {
+            method: Method::Get,
+            path: path.to_string(),
+            operation_id: None,
+            description: String::new(),
+            parameters: Vec::new(),
+            body: None,
+            responses: Vec::new(),
+        };
+
+        assert_eq!(
+            op("/pets/{id}/toys/{toy}.json").path_parts(),
+            vec![
+                PathPart::Literal("/pets/"),
+                PathPart::Placeholder("id"),
+                PathPart::Literal("/toys/"),
+                PathPart::Placeholder("toy"),
+                PathPart::Literal(".json"),
+            ]
+        );
+        assert_eq!(op("/a}{b").path_parts(), vec![PathPart::Literal("/a}{b")]);
+    }
+}
@@ -0,0 +1,199 @@
+use anyhow::{anyhow, bail, Context, Result};
+use url::Url;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SourceFormat {
+    Json,
+    Yaml,
+}
+
+impl SourceFormat {
+    pub fn from_path(path: &str) -> Option<SourceFormat> {
+        let path = path.to_lowercase();
+
+        if path.ends_with(".yaml") || path.ends_with(".yml") {
+            return Some(SourceFormat::Yaml);
+        }
+
+        if path.ends_with(".json") {
+            return Some(SourceFormat::Json);
+        }
+
+        None
+    }
+
+    pub fn from_content_type(content_type: &str) -> Option<SourceFormat> {
+        let content_type = content_type.to_lowercase();
+
+        if content_type.contains("yaml") {
+            return Some(SourceFormat::Yaml);
+        }
+
+        if content_type.contains("json") {
+            return Some(SourceFormat::Json);
+        }
+
+        None
+    }
+
+    /// JSON documents always start with an object or array, anything else is
+    /// treated as YAML.
+    pub fn sniff(data: &str) -> SourceFormat {
+        match data.trim_start().chars().next() {
+            Some('{') | Some('[') => SourceFormat::Json,
+            _ => SourceFormat::Yaml,
+        }
+    }
+}
+
+/// Turns the configured source into a URL so relative references can be
+/// joined against it, whether it points to a file or a remote document.
+pub fn source_url(source: &str) -> Result<Url> {
+    if let Ok(url) = Url::parse(source) {
+        if url.scheme() != "file" && url.cannot_be_a_base() {
+            bail!("Unsupported source: {}", source);
+        }
+
+        return Ok(url);
+    }
+
+    let path = std::fs::canonicalize(source).context("Unable to read file")?;
+
+    Url::from_file_path(&path).map_err(|_| anyhow!("Invalid file path: {}", path.display()))
+}
+
+/// Reads a spec from a URL or a file and parses it into a JSON value,
+/// whatever format it was written in.
+pub fn load(url: &Url) -> Result<serde_json::Value> {
+    let (data, format) = fetch(url)?;
+
+    parse_document(&data, format.unwrap_or_else(|| SourceFormat::sniff(&data)))
+}
+
+fn fetch(url: &Url) -> Result<(String, Option<SourceFormat>)> {
+    if url.scheme() == "file" {
+        let path = url
+            .to_file_path()
+            .map_err(|_| anyhow!("Invalid file URL: {}", url))?;
+        let file_contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("Unable to read file {}", path.display()))?;
+
+        return Ok((file_contents, SourceFormat::from_path(url.path())));
+    }
+
+    let response = reqwest::blocking::get(url.as_str())
+        .and_then(|response| response.error_for_status())
+        .with_context(|| format!("Failed to fetch data from {}", url))?;
+
+    let format = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(SourceFormat::from_content_type)
+        .or_else(|| SourceFormat::from_path(url.path()));
+
+    let body = response.text().context("Failed to read response body")?;
+
+    Ok((body, format))
+}
+
+pub fn parse_document(data: &str, format: SourceFormat) -> Result<serde_json::Value> {
+    match format {
+        SourceFormat::Json => serde_json::from_str(data).context("Unable to parse JSON"),
+        SourceFormat::Yaml => {
+            let value: serde_yaml::Value =
+                serde_yaml::from_str(data).context("Unable to parse YAML")?;
+
+            Ok(yaml_to_json(value))
+        }
+    }
+}
+
+/// YAML allows non-string mapping keys (e.g. `200:` response codes), which
+/// JSON doesn't, so keys are stringified on the way through.
+fn yaml_to_json(value: serde_yaml::Value) -> serde_json::Value {
+    match value {
+        serde_yaml::Value::Null => serde_json::Value::Null,
+        serde_yaml::Value::Bool(v) => serde_json::Value::Bool(v),
+        serde_yaml::Value::Number(v) => {
+            if let Some(n) = v.as_i64() {
+                serde_json::Value::from(n)
+            } else if let Some(n) = v.as_u64() {
+                serde_json::Value::from(n)
+            } else {
+                v.as_f64()
+                    .and_then(serde_json::Number::from_f64)
+                    .map(serde_json::Value::Number)
+                    .unwrap_or(serde_json::Value::Null)
+            }
+        }
+        serde_yaml::Value::String(v) => serde_json::Value::String(v),
+        serde_yaml::Value::Sequence(values) => {
+            serde_json::Value::Array(values.into_iter().map(yaml_to_json).collect())
+        }
+        serde_yaml::Value::Mapping(mapping) => serde_json::Value::Object(
+            mapping
+                .into_iter()
+                .map(|(key, value)| (yaml_key_to_string(key), yaml_to_json(value)))
+                .collect(),
+        ),
+        serde_yaml::Value::Tagged(tagged) => yaml_to_json(tagged.value),
+    }
+}
+
+fn yaml_key_to_string(key: serde_yaml::Value) -> String {
+    match key {
+        serde_yaml::Value::String(v) => v,
+        serde_yaml::Value::Bool(v) => v.to_string(),
+        serde_yaml::Value::Number(v) => v.to_string(),
+        serde_yaml::Value::Null => "null".to_string(),
+        other => serde_yaml::to_string(&other)
+            .unwrap_or_default()
+            .trim()
+            .to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn detect_format_from_hints() {
+        assert_eq!(
+            SourceFormat::from_path("specs/openapi.YML"),
+            Some(SourceFormat::Yaml)
+        );
+        assert_eq!(
+            SourceFormat::from_path("example.json"),
+            Some(SourceFormat::Json)
+        );
+        assert_eq!(SourceFormat::from_path("spec"), None);
+        assert_eq!(
+            SourceFormat::from_content_type("application/x-yaml; charset=utf-8"),
+            Some(SourceFormat::Yaml)
+        );
+        assert_eq!(SourceFormat::from_content_type("text/plain"), None);
+    }
+
+    #[test]
+    fn sniff_format_from_content() {
+        assert_eq!(
+            SourceFormat::sniff("  \n{\"swagger\": \"2.0\"}"),
+            SourceFormat::Json
+        );
+        assert_eq!(SourceFormat::sniff("openapi: 3.0.0\n"), SourceFormat::Yaml);
+    }
+
+    #[test]
+    fn parse_yaml_with_numeric_keys() {
+        let data = "openapi: 3.0.0\npaths:\n  /pets:\n    get:\n      responses:\n        200:\n          description: OK\n";
+
+        let document = parse_document(data, SourceFormat::Yaml).unwrap();
+
+        assert_eq!(
+            document["paths"]["/pets"]["get"]["responses"]["200"]["description"],
+            "OK"
+        );
+    }
+}
@@ -1,9 +1,18 @@
 use anyhow::{bail, Ok, Result};
 
 mod cli;
+mod generator;
+mod ir;
+mod loader;
+mod naming;
+mod options;
 mod parser;
+mod python;
+mod resolver;
+mod rust;
 mod sanitizer;
 mod template;
+mod zod;
 
 fn main() -> Result<()> {
     env_logger::init();
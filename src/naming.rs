@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+
+/// Casing applied to generated identifiers.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NamingStyle {
+    Camel,
+    Pascal,
+    Snake,
+    /// Leaves names as they are derived from the spec.
+    #[default]
+    Keep,
+}
+
+impl NamingStyle {
+    pub fn apply(&self, name: &str) -> String {
+        let words = split_words(name);
+
+        let result = match self {
+            NamingStyle::Keep => return name.to_string(),
+            NamingStyle::Snake => words
+                .iter()
+                .map(|word| word.to_lowercase())
+                .collect::<Vec<_>>()
+                .join("_"),
+            NamingStyle::Pascal => words.iter().map(|word| capitalize(word)).collect(),
+            NamingStyle::Camel => words
+                .iter()
+                .enumerate()
+                .map(|(idx, word)| match idx {
+                    0 => word.to_lowercase(),
+                    _ => capitalize(word),
+                })
+                .collect(),
+        };
+
+        match result.starts_with(|c: char| c.is_ascii_digit()) {
+            true => format!("_{}", result),
+            false => result,
+        }
+    }
+}
+
+/// Splits on separators and case changes, keeping acronyms together:
+/// `HTTPResponse_code` -> `HTTP`, `Response`, `code`.
+fn split_words(name: &str) -> Vec<String> {
+    let chars: Vec<char> = name.chars().collect();
+    let mut words = Vec::new();
+    let mut current = String::new();
+
+    chars.iter().enumerate().for_each(|(idx, &c)| {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            return;
+        }
+
+        if c.is_uppercase() && !current.is_empty() {
+            let prev = chars[idx - 1];
+            let next_is_lower = chars.get(idx + 1).is_some_and(|next| next.is_lowercase());
+
+            if prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_is_lower) {
+                words.push(std::mem::take(&mut current));
+            }
+        }
+
+        current.push(c);
+    });
+
+    if !current.is_empty() {
+        words.push(current);
+    }
+
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn apply_naming_styles() {
+        assert_eq!(
+            NamingStyle::Camel.apply("get_apps_by_machines_by"),
+            "getAppsByMachinesBy"
+        );
+        assert_eq!(
+            NamingStyle::Pascal.apply("_apps_app_name_Params"),
+            "AppsAppNameParams"
+        );
+        assert_eq!(
+            NamingStyle::Snake.apply("HTTPResponseCode"),
+            "http_response_code"
+        );
+        assert_eq!(NamingStyle::Camel.apply("machine-ID"), "machineId");
+        assert_eq!(NamingStyle::Pascal.apply("2fa_code"), "_2faCode");
+        assert_eq!(NamingStyle::Keep.apply("api_Machine"), "api_Machine");
+    }
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+use crate::naming::NamingStyle;
+
+/// Settings that change the shape of the generated code, read from
+/// `api-gen.json` next to `source` and `path`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Options {
+    /// Adds `| undefined` to optional properties, for projects compiled with
+    /// `exactOptionalPropertyTypes`.
+    pub exact_optional_property_types: bool,
+    /// Names functions and their types after the `operationId` of each
+    /// operation instead of its method and path.
+    pub use_operation_id: bool,
+    /// Casing of generated function names.
+    pub function_case: NamingStyle,
+    /// Casing of generated type names.
+    pub type_case: NamingStyle,
+    /// Casing of properties and parameters. Renamed keys are mapped back to
+    /// their wire names when requests are sent and responses are read.
+    pub property_case: NamingStyle,
+    /// Emits definitions, properties and operations in the order they appear
+    /// in the spec instead of sorting them by name.
+    pub preserve_spec_order: bool,
+    /// Parses successful responses through their Zod schema, only used by the
+    /// `zod` target.
+    pub validate_responses: bool,
+}
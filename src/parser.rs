@@ -1,20 +1,225 @@
+use anyhow::{bail, Context, Result};
+use indexmap::IndexMap;
 use log::warn;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use url::Url;
 
-type DefinitionMap = HashMap<String, Definition>;
+use crate::ir;
+
+type DefinitionMap = IndexMap<String, Definition>;
 pub type OperationResponseMap = HashMap<String, ResponsePayload>;
-pub type DefinitionPropertyMap = HashMap<String, DefinitionProperty>;
-type KV = HashMap<String, String>;
+pub type DefinitionPropertyMap = IndexMap<String, Definition>;
+type MediaTypeMap = HashMap<String, MediaType>;
+
+/// Parses a Swagger 2.0 or OpenAPI 3.x document. OpenAPI 3 constructs are
+/// folded into their Swagger 2 equivalents, then the document is lowered to
+/// the spec-agnostic `ir::Api` the emitters work with. `source` is where the
+/// document was loaded from, relative server URLs are resolved against it.
+pub fn parse_schema(document: serde_json::Value, source: &Url) -> Result<ir::Api> {
+    let schema: Schema = serde_json::from_value(document).context("Unable to parse schema")?;
+
+    let mut api = schema.normalize()?.lower();
+    api.base_url = resolve_base_url(&api.base_url, source)?;
+    send_files_as_multipart(&mut api);
+
+    Ok(api)
+}
+
+/// Files can't be URL encoded, form bodies holding one are sent as
+/// `multipart/form-data` instead.
+fn send_files_as_multipart(api: &mut ir::Api) {
+    let mut operations = std::mem::take(&mut api.operations);
+    let mut webhooks = std::mem::take(&mut api.webhooks);
+
+    operations
+        .iter_mut()
+        .chain(webhooks.values_mut().flatten())
+        .for_each(|op| {
+            if let Some(body) = op.body.as_mut() {
+                if body.encoding == ir::BodyEncoding::UrlEncoded && api.contains_file(&body.ty) {
+                    warn!(
+                        "The URL encoded body of {} {} holds a file, it is sent as multipart/form-data",
+                        op.method.as_str(),
+                        op.path
+                    );
+                    body.encoding = ir::BodyEncoding::Multipart;
+                }
+            }
+        });
+
+    api.operations = operations;
+    api.webhooks = webhooks;
+}
+
+/// Servers relative to the document, or a missing one, are resolved against
+/// the URL the spec was fetched from. A local spec has nothing to resolve
+/// them against, its base URL stays relative.
+fn resolve_base_url(base_url: &str, source: &Url) -> Result<String> {
+    if Url::parse(base_url).is_ok() {
+        return Ok(base_url.to_string());
+    }
+
+    if source.scheme() == "file" {
+        warn!(
+            "The spec has no absolute server URL (found `{}`), requests are sent relative to the current page or to the base URL given to the client",
+            base_url
+        );
+        return Ok(base_url.trim_end_matches('/').to_string());
+    }
+
+    // An empty URL is the default server `/`, not the document itself.
+    let url = source
+        .join(match base_url {
+            "" => "/",
+            url => url,
+        })
+        .with_context(|| format!("Invalid server URL: {}", base_url))?;
+
+    Ok(url.as_str().trim_end_matches('/').to_string())
+}
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Schema {
+    pub swagger: Option<String>,
+    pub openapi: Option<String>,
+    #[serde(default)]
     schemes: Vec<String>,
+    #[serde(default)]
     pub host: String,
-    #[serde(rename = "basePath")]
+    #[serde(rename = "basePath", default)]
     pub base_path: String,
-    pub paths: HashMap<String, Path>,
+    #[serde(default)]
+    pub servers: Vec<Server>,
+    #[serde(default)]
+    consumes: Vec<String>,
+    #[serde(default)]
+    produces: Vec<String>,
+    /// Optional since OpenAPI 3.1, a document may only describe webhooks.
+    #[serde(default)]
+    pub paths: IndexMap<String, Path>,
+    #[serde(default)]
     pub definitions: DefinitionMap,
+    pub components: Option<Components>,
+    #[serde(default)]
+    pub webhooks: IndexMap<String, Path>,
+}
+
+impl Schema {
+    pub fn is_openapi_3(&self) -> bool {
+        self.openapi
+            .as_ref()
+            .is_some_and(|version| version.starts_with("3."))
+    }
+
+    pub fn base_url(&self) -> String {
+        if self.is_openapi_3() {
+            return match self.servers.first() {
+                Some(server) => server.resolve_url(),
+                None => String::new(),
+            };
+        }
+
+        match self.host.as_str() {
+            "" => self.base_path.trim_end_matches('/').to_string(),
+            host => format!("https://{}{}", host, self.base_path),
+        }
+    }
+
+    fn lower(&self) -> ir::Api {
+        ir::Api {
+            base_url: self.base_url(),
+            types: self
+                .definitions
+                .iter()
+                .map(|(key, def)| (key.clone(), def.lower()))
+                .collect(),
+            operations: self
+                .paths
+                .iter()
+                .flat_map(|(key, path)| path.lower(key))
+                .collect(),
+            webhooks: self
+                .webhooks
+                .iter()
+                .map(|(key, path)| (key.clone(), path.lower(key)))
+                .collect(),
+        }
+    }
+
+    fn normalize(mut self) -> Result<Schema> {
+        match (&self.swagger, &self.openapi) {
+            (_, Some(version)) if !self.is_openapi_3() => {
+                bail!("Unsupported OpenAPI version: {}", version)
+            }
+            (None, None) => warn!("Document has no `swagger` or `openapi` version field"),
+            _ => (),
+        }
+
+        if let Some(components) = self.components.take() {
+            self.definitions.extend(components.schemas);
+        }
+
+        let defs: Vec<(String, Definition)> = self
+            .definitions
+            .values_mut()
+            .flat_map(|def| def.take_defs())
+            .collect();
+        defs.into_iter().for_each(|(key, def)| {
+            if self.definitions.contains_key(&key) {
+                warn!("`$defs` entry {} shadows an existing definition", key);
+                return;
+            }
+
+            self.definitions.insert(key, def);
+        });
+
+        apply_discriminators(&mut self.definitions);
+
+        self.paths
+            .values_mut()
+            .chain(self.webhooks.values_mut())
+            .for_each(|path| path.merge_parameters());
+
+        self.paths
+            .values_mut()
+            .chain(self.webhooks.values_mut())
+            .flat_map(|path| path.operations_mut())
+            .for_each(|op| op.normalize(&self.consumes, &self.produces));
+
+        Ok(self)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Server {
+    pub url: String,
+    #[serde(default)]
+    variables: HashMap<String, ServerVariable>,
+}
+
+impl Server {
+    fn resolve_url(&self) -> String {
+        let url = self
+            .variables
+            .iter()
+            .fold(self.url.clone(), |acc, (name, variable)| {
+                acc.replace(&format!("{{{name}}}"), &variable.default)
+            });
+
+        url.trim_end_matches('/').to_string()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+struct ServerVariable {
+    default: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Components {
+    #[serde(default)]
+    schemas: DefinitionMap,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -24,124 +229,360 @@ pub struct Path {
     pub put: Option<Operation>,
     pub delete: Option<Operation>,
     pub patch: Option<Operation>,
+    pub head: Option<Operation>,
+    pub options: Option<Operation>,
+    pub trace: Option<Operation>,
+    /// Parameters shared by every operation of the path.
+    #[serde(default)]
+    parameters: Vec<OperationParameter>,
+}
+
+impl Path {
+    fn lower(&self, key: &str) -> Vec<ir::Operation> {
+        [
+            (ir::Method::Get, &self.get),
+            (ir::Method::Post, &self.post),
+            (ir::Method::Put, &self.put),
+            (ir::Method::Delete, &self.delete),
+            (ir::Method::Patch, &self.patch),
+            (ir::Method::Head, &self.head),
+            (ir::Method::Options, &self.options),
+            (ir::Method::Trace, &self.trace),
+        ]
+        .into_iter()
+        .filter_map(|(method, op)| Some(op.as_ref()?.lower(method, key)))
+        .collect()
+    }
+
+    fn operations_mut(&mut self) -> impl Iterator<Item = &mut Operation> {
+        [
+            &mut self.get,
+            &mut self.post,
+            &mut self.put,
+            &mut self.delete,
+            &mut self.patch,
+            &mut self.head,
+            &mut self.options,
+            &mut self.trace,
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    /// Copies the shared parameters into each operation, unless the
+    /// operation overrides them (same `name` and `in`).
+    fn merge_parameters(&mut self) {
+        let shared = std::mem::take(&mut self.parameters);
+
+        if shared.is_empty() {
+            return;
+        }
+
+        self.operations_mut().for_each(|op| {
+            let parameters = op.parameters.get_or_insert_with(Vec::new);
+
+            let inherited: Vec<OperationParameter> = shared
+                .iter()
+                .filter(|param| {
+                    !parameters
+                        .iter()
+                        .any(|own| own.name == param.name && own.in_field == param.in_field)
+                })
+                .cloned()
+                .collect();
+
+            parameters.splice(0..0, inherited);
+        });
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Operation {
+    #[serde(default)]
     pub description: String,
+    #[serde(rename = "operationId")]
+    pub operation_id: Option<String>,
     pub parameters: Option<Vec<OperationParameter>>,
+    #[serde(rename = "requestBody")]
+    request_body: Option<RequestBody>,
     pub responses: OperationResponseMap,
+    consumes: Option<Vec<String>>,
+    produces: Option<Vec<String>>,
+    /// How the body has to be sent, derived from the media types the
+    /// operation consumes.
+    #[serde(skip)]
+    body_encoding: ir::BodyEncoding,
 }
 
 impl Operation {
-    pub fn parse_query(&self) -> String {
-        let mut builder = String::new();
+    fn lower(&self, method: ir::Method, key: &str) -> ir::Operation {
+        let parameters = self.parameters.iter().flatten();
+
+        let mut responses: Vec<(&String, &ResponsePayload)> = self.responses.iter().collect();
+        responses.sort_by_key(|(status, _)| *status);
+
+        ir::Operation {
+            method,
+            path: key.to_string(),
+            operation_id: self.operation_id.clone(),
+            description: self.description.clone(),
+            parameters: parameters
+                .clone()
+                .filter_map(|param| param.lower())
+                .collect(),
+            body: parameters
+                .clone()
+                .find(|param| param.in_field == OperationParameterField::Body)
+                .map(|param| ir::Body {
+                    ty: match &param.ref_field {
+                        Some(schema) => schema.lower(),
+                        None => {
+                            warn!("No schema found for body");
+                            ir::Type::Any
+                        }
+                    },
+                    encoding: self.body_encoding,
+                }),
+            responses: responses
+                .into_iter()
+                .map(|(status, response)| ir::Response {
+                    status: status.clone(),
+                    ty: response.schema.as_ref().map(Definition::lower),
+                    encoding: response
+                        .media_type
+                        .as_deref()
+                        .map(response_encoding)
+                        .unwrap_or_default(),
+                })
+                .collect(),
+        }
+    }
 
-        let params = if self.parameters.is_some() {
-            self.parameters.clone().unwrap()
-        } else {
-            Vec::new()
-        };
+    /// Rewrites OpenAPI 3 request bodies, parameter schemas and response
+    /// content into the Swagger 2 layout used by the templates.
+    fn normalize(&mut self, consumes: &[String], produces: &[String]) {
+        if let Some(body) = self.request_body.take() {
+            let media = pick_media_type(&body.content);
+
+            self.body_encoding = media
+                .and_then(|(key, _)| body_encoding(key))
+                .unwrap_or_default();
+
+            self.parameters
+                .get_or_insert_with(Vec::new)
+                .push(OperationParameter::body(
+                    body.description,
+                    body.required,
+                    media.and_then(|(_, media)| media.schema.clone()),
+                ));
+        }
 
-        params
-            .iter()
-            .filter_map(|p| match p.in_field.clone() {
-                OperationParameterField::Query => Some(p),
-                _ => None,
-            })
+        self.parameters
+            .iter_mut()
+            .flatten()
+            .filter(|param| param.in_field != OperationParameterField::Body)
+            .for_each(|param| match &param.ref_field {
+                // OpenAPI 3 parameters default to `form` in the query string
+                // and cookies, `simple` everywhere else.
+                Some(_) if param.style.is_none() => {
+                    param.style = Some(match param.in_field {
+                        OperationParameterField::Query | OperationParameterField::Cookie => {
+                            "form".to_string()
+                        }
+                        _ => "simple".to_string(),
+                    });
+                }
+                Some(_) => (),
+                None => {
+                    param.ref_field = param.type_field.clone().map(|type_field| Definition {
+                        type_field: Some(TypeSet::Single(type_field)),
+                        items: param.items.clone(),
+                        _enum: param._enum.clone(),
+                        ..Default::default()
+                    });
+                }
+            });
+
+        self.normalize_form_data(consumes);
+
+        self.parameters
+            .iter_mut()
+            .flatten()
+            .filter(|param| param.type_field.is_none())
             .for_each(|param| {
-                if !builder.is_empty() {
-                    builder.push_str(", ");
+                if param
+                    .ref_field
+                    .as_ref()
+                    .is_some_and(|schema| schema.is_nullable())
+                {
+                    param.nullable.x_nullable = Some(true);
                 }
 
-                builder.push_str(param.name.as_str());
-                builder.push_str(": ");
-                builder.push_str(self.parse_inner_type(param).as_str());
+                param.type_field = param
+                    .ref_field
+                    .as_ref()
+                    .and_then(|schema| schema.type_field.as_ref())
+                    .and_then(|types| {
+                        types
+                            .iter()
+                            .find(|t| !matches!(t, DefinitionPropertyType::Null))
+                            .cloned()
+                    });
             });
 
-        return builder;
-    }
+        let produces = self.produces.as_deref().unwrap_or(produces);
 
-    pub fn parse_path(&self) -> String {
-        let mut builder = String::new();
+        self.responses
+            .values_mut()
+            .for_each(|response| match pick_media_type(&response.content) {
+                Some((key, media)) => {
+                    if response.schema.is_none() {
+                        response.schema = media.schema.clone();
+                    }
+                    response.media_type = Some(key.clone());
+                }
+                None => {
+                    response.media_type =
+                        produced_media_type(produces, response.schema.as_ref()).cloned();
+                }
+            });
+    }
 
-        let params = if self.parameters.is_some() {
-            self.parameters.clone().unwrap()
-        } else {
-            Vec::new()
+    /// Swagger 2 declares form fields as separate `formData` parameters, they
+    /// are merged into a single object body.
+    fn normalize_form_data(&mut self, consumes: &[String]) {
+        let Some(parameters) = self.parameters.as_mut() else {
+            return;
         };
 
-        params
-            .iter()
-            .filter_map(|p| match p.in_field.clone() {
-                OperationParameterField::Path => Some(p),
-                _ => None,
-            })
-            .for_each(|param| {
-                if !builder.is_empty() {
-                    builder.push_str(", ");
-                }
+        let (fields, rest): (Vec<_>, Vec<_>) = std::mem::take(parameters)
+            .into_iter()
+            .partition(|param| param.in_field == OperationParameterField::FormData);
+        *parameters = rest;
+
+        if fields.is_empty() {
+            return;
+        }
 
-                builder.push_str(param.name.as_str());
-                builder.push_str(": ");
+        let has_file = fields
+            .iter()
+            .any(|field| matches!(field.type_field, Some(DefinitionPropertyType::File)));
 
-                builder.push_str(self.parse_inner_type(param).as_str());
+        self.body_encoding = self
+            .consumes
+            .as_deref()
+            .unwrap_or(consumes)
+            .iter()
+            .find_map(|media_type| body_encoding(media_type))
+            .unwrap_or(match has_file {
+                true => ir::BodyEncoding::Multipart,
+                false => ir::BodyEncoding::UrlEncoded,
             });
 
-        return builder;
-    }
+        let required: Vec<String> = fields
+            .iter()
+            .filter(|field| field.required.unwrap_or(false))
+            .map(|field| field.name.clone())
+            .collect();
 
-    fn parse_inner_type(&self, operation: &OperationParameter) -> String {
-        let mut tokens = Vec::with_capacity(2);
+        let properties: DefinitionPropertyMap = fields
+            .into_iter()
+            .map(|field| {
+                let schema = Definition {
+                    description: Some(field.description).filter(|d| !d.is_empty()),
+                    nullable: field.nullable,
+                    ..field.ref_field.unwrap_or_default()
+                };
 
-        match (&operation.type_field, operation.required) {
-            (None, None) => tokens.push("never"),
-            (None, Some(_)) => tokens.push("never"),
-            (Some(field), None) => {
-                match field {
-                    OperationParameterType::String => tokens.push("string"),
-                    OperationParameterType::Integer => tokens.push("number"),
-                    OperationParameterType::Boolean => tokens.push("boolean"),
-                    _ => todo!("[not impl] -> {:?}", field),
-                }
+                (field.name, schema)
+            })
+            .collect();
 
-                tokens.push("undefined");
-            }
-            (Some(field), Some(is_req)) => {
-                match field {
-                    OperationParameterType::String => tokens.push("string"),
-                    OperationParameterType::Integer => tokens.push("number"),
-                    OperationParameterType::Boolean => tokens.push("boolean"),
-                    _ => todo!("[not impl] -> {:?}", field),
-                }
+        parameters.push(OperationParameter::body(
+            String::new(),
+            Some(!required.is_empty()),
+            Some(Definition {
+                type_field: Some(TypeSet::Single(DefinitionPropertyType::Object)),
+                properties: Some(properties),
+                required,
+                ..Default::default()
+            }),
+        ));
+    }
+}
 
-                if !is_req {
-                    tokens.push("undefined | null");
-                }
-            }
+fn body_encoding(media_type: &str) -> Option<ir::BodyEncoding> {
+    match media_type {
+        "multipart/form-data" => Some(ir::BodyEncoding::Multipart),
+        "application/x-www-form-urlencoded" => Some(ir::BodyEncoding::UrlEncoded),
+        _ => None,
+    }
+}
+
+/// How a payload of `media_type` is read. Wildcards don't say anything about
+/// the payload, it is assumed to be JSON like when no media type is declared.
+fn response_encoding(media_type: &str) -> ir::ResponseEncoding {
+    let essence = media_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    match essence.as_str() {
+        "application/json" | "*/*" => ir::ResponseEncoding::Json,
+        essence if essence.ends_with("+json") => ir::ResponseEncoding::Json,
+        essence
+            if essence.starts_with("text/")
+                || essence == "application/xml"
+                || essence.ends_with("+xml") =>
+        {
+            ir::ResponseEncoding::Text
         }
+        _ => ir::ResponseEncoding::Binary,
+    }
+}
 
-        return tokens.join(" | ");
+/// Swagger 2 lists the media types once per operation. Responses are JSON
+/// when it is listed, unless they are a bare string or file and another media
+/// type is produced too, e.g. a download answering errors in JSON.
+fn produced_media_type<'a>(
+    produces: &'a [String],
+    schema: Option<&Definition>,
+) -> Option<&'a String> {
+    let raw = schema.is_some_and(|schema| {
+        matches!(
+            schema.type_field,
+            Some(TypeSet::Single(
+                DefinitionPropertyType::String | DefinitionPropertyType::File
+            ))
+        )
+    });
+    let (json, other): (Vec<&String>, Vec<&String>) = produces
+        .iter()
+        .partition(|media_type| response_encoding(media_type) == ir::ResponseEncoding::Json);
+
+    match (json.first(), other.first()) {
+        (Some(_), Some(other)) if raw => Some(other),
+        (Some(json), _) => Some(json),
+        (None, other) => other.copied(),
     }
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
-pub enum OperationParameterType {
-    #[serde(rename = "string")]
-    String,
-    #[serde(rename = "integer")]
-    Integer,
-    #[serde(rename = "number")]
-    Number,
-    #[serde(rename = "boolean")]
-    Boolean,
-    #[serde(rename = "array")]
-    Array,
-    #[serde(rename = "object")]
-    Object,
+/// Picks the JSON media type, falling back to the first one declared when the
+/// operation doesn't speak JSON.
+fn pick_media_type(content: &MediaTypeMap) -> Option<(&String, &MediaType)> {
+    let mut keys: Vec<&String> = content.keys().collect();
+    keys.sort();
+
+    keys.iter()
+        .find(|key| key.as_str() == "application/json")
+        .or_else(|| keys.iter().find(|key| key.contains("json")))
+        .or_else(|| keys.first())
+        .and_then(|key| content.get_key_value(*key))
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub enum OperationParameterField {
     #[serde(rename = "query")]
     Query,
@@ -149,293 +590,723 @@ pub enum OperationParameterField {
     Body,
     #[serde(rename = "path")]
     Path,
+    #[serde(rename = "header")]
+    Header,
+    #[serde(rename = "cookie")]
+    Cookie,
+    #[serde(rename = "formData")]
+    FormData,
+}
+
+impl OperationParameterField {
+    fn location(&self) -> Option<ir::ParameterLocation> {
+        match self {
+            OperationParameterField::Query => Some(ir::ParameterLocation::Query),
+            OperationParameterField::Path => Some(ir::ParameterLocation::Path),
+            OperationParameterField::Header => Some(ir::ParameterLocation::Header),
+            OperationParameterField::Cookie => Some(ir::ParameterLocation::Cookie),
+            OperationParameterField::Body | OperationParameterField::FormData => None,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct OperationParameter {
     #[serde(rename = "type")]
-    pub type_field: Option<OperationParameterType>,
+    pub type_field: Option<DefinitionPropertyType>,
+    #[serde(default)]
     description: String,
     pub name: String,
     #[serde(rename = "in")]
     pub in_field: OperationParameterField,
     pub required: Option<bool>,
     #[serde(rename = "schema")]
-    pub ref_field: Option<SchemaRef>,
+    pub ref_field: Option<Definition>,
+    #[serde(flatten)]
+    nullable: Nullable,
+    items: Option<Box<Definition>>,
+    #[serde(rename = "enum")]
+    _enum: Option<Vec<serde_json::Value>>,
+    /// Swagger 2 array serialization.
+    #[serde(rename = "collectionFormat")]
+    collection_format: Option<String>,
+    /// OpenAPI 3 serialization.
+    style: Option<String>,
+    explode: Option<bool>,
 }
 
 impl OperationParameter {
-    pub fn parse_body(&self) -> String {
-        match &self.ref_field {
-            Some(ref schema) => match schema.type_ref {
-                Some(ref _type) => {
-                    return clear_ref(_type);
-                }
-                None => {
-                    return "unknown".to_string();
-                }
-            },
-            None => {
-                warn!("No schema found for body");
-                return "unknown".to_string();
-            }
+    fn body(description: String, required: Option<bool>, schema: Option<Definition>) -> Self {
+        OperationParameter {
+            type_field: None,
+            description,
+            name: "body".to_string(),
+            in_field: OperationParameterField::Body,
+            required,
+            ref_field: schema,
+            nullable: Nullable::default(),
+            items: None,
+            _enum: None,
+            collection_format: None,
+            style: None,
+            explode: None,
         }
     }
-}
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct ResponsePayload {
-    description: String,
-    pub schema: Option<SchemaRef>,
-}
+    /// Body parameters are lowered separately.
+    fn lower(&self) -> Option<ir::Parameter> {
+        Some(ir::Parameter {
+            name: self.name.clone(),
+            location: self.in_field.location()?,
+            required: self.required.unwrap_or(false),
+            // Parameters explicitly marked as optional have always accepted
+            // `null` as well.
+            nullable: self.nullable.is_set() || self.required == Some(false),
+            ty: match &self.ref_field {
+                Some(schema) => schema.lower(),
+                None => ir::Type::Never,
+            },
+            serialization: self.serialization(),
+        })
+    }
+
+    /// How the value has to be put in the URL, only set for array and object
+    /// parameters.
+    fn serialization(&self) -> Option<ir::Serialization> {
+        let is_object = match &self.type_field {
+            Some(DefinitionPropertyType::Array) => false,
+            Some(DefinitionPropertyType::Object) => true,
+            _ => return None,
+        };
 
-impl ResponsePayload {
-    pub fn parse_response(&self) -> String {
-        match self.schema {
-            Some(ref schema) => {
-                let mut builder = String::new();
+        if !matches!(
+            self.in_field,
+            OperationParameterField::Query | OperationParameterField::Path
+        ) {
+            return None;
+        }
 
-                match schema.type_ref {
-                    Some(ref _type) => {
-                        builder.push_str("Promise<");
-                        builder.push_str(clear_ref(_type).as_str());
-                        builder.push_str(">");
-                    }
-                    None => {
-                        builder.push_str("Promise<unknown>");
-                    }
+        if let Some(format) = &self.collection_format {
+            return Some(match format.as_str() {
+                "csv" => ir::Serialization::Csv,
+                "ssv" => ir::Serialization::Ssv,
+                "tsv" => ir::Serialization::Tsv,
+                "pipes" => ir::Serialization::Pipes,
+                "multi" => ir::Serialization::Multi,
+                other => {
+                    warn!("Unsupported collectionFormat {} on {}", other, self.name);
+                    ir::Serialization::Csv
                 }
+            });
+        }
 
-                return builder;
+        // `form` is the only style that explodes by default.
+        let style = self.style.as_deref().unwrap_or("csv");
+        let explode = self.explode.unwrap_or(style == "form");
+
+        Some(match (style, explode) {
+            ("form", true) if is_object => ir::Serialization::Form,
+            ("form" | "spaceDelimited" | "pipeDelimited", true) => ir::Serialization::Multi,
+            ("spaceDelimited", false) => ir::Serialization::Ssv,
+            ("pipeDelimited", false) => ir::Serialization::Pipes,
+            ("deepObject", _) => ir::Serialization::DeepObject,
+            ("form" | "simple" | "csv", _) => ir::Serialization::Csv,
+            (other, _) => {
+                warn!("Unsupported style {} on {}", other, self.name);
+                ir::Serialization::Csv
             }
-            None => {
-                warn!("No schema found for response");
-                return "Promise<unknown>".to_string();
-            }
-        }
+        })
     }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct SchemaRef {
-    #[serde(rename = "$ref")]
-    type_ref: Option<String>,
-    items: Option<SchemaRefItems>,
+pub struct RequestBody {
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    content: MediaTypeMap,
+    required: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
-struct SchemaRefItems {
-    #[serde(rename = "$ref")]
-    _ref: Option<String>,
+pub struct MediaType {
+    schema: Option<Definition>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
-enum DefinitionType {
-    #[serde(rename = "object")]
-    Object,
-    #[serde(rename = "string")]
-    String,
+pub struct ResponsePayload {
+    #[serde(default)]
+    description: String,
+    pub schema: Option<Definition>,
+    #[serde(default)]
+    content: MediaTypeMap,
+    /// Media type of the payload, picked from `content` or the media types
+    /// the operation produces.
+    #[serde(skip)]
+    media_type: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct Definition {
-    #[serde(rename = "type")]
-    type_field: DefinitionType,
-    pub properties: Option<DefinitionPropertyMap>,
-    #[serde(rename = "enum")]
-    pub _enum: Option<Vec<String>>,
+/// Swagger 2 marks nullable schemas with the `x-nullable` extension,
+/// OpenAPI 3.0 with `nullable`. OpenAPI 3.1 lists `"null"` in `type`
+/// instead, which is handled with the rest of the type set.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Nullable {
+    nullable: Option<bool>,
+    #[serde(rename = "x-nullable")]
+    x_nullable: Option<bool>,
 }
 
+impl Nullable {
+    fn is_set(&self) -> bool {
+        self.nullable.unwrap_or(false) || self.x_nullable.unwrap_or(false)
+    }
+}
+
+/// `additionalProperties` is either a flag or the schema every undeclared
+/// property has to match.
 #[derive(Debug, Deserialize, Serialize, Clone)]
-enum DefinitionPropertyType {
-    #[serde(rename = "string")]
-    String,
-    #[serde(rename = "array")]
-    Array,
-    #[serde(rename = "integer")]
-    Integer,
-    #[serde(rename = "number")]
-    Number,
-    #[serde(rename = "boolean")]
-    Boolean,
-    #[serde(rename = "object")]
-    Object,
+#[serde(untagged)]
+pub enum AdditionalProperties {
+    Allowed(bool),
+    Schema(Box<Definition>),
 }
 
+/// JSON Schema 2020-12 (OpenAPI 3.1) allows `type` to list several types,
+/// e.g. `["string", "null"]`.
 #[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct DefinitionProperty {
+#[serde(untagged)]
+pub enum TypeSet<T> {
+    Single(T),
+    Multiple(Vec<T>),
+}
+
+impl<T> TypeSet<T> {
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        match self {
+            TypeSet::Single(value) => std::slice::from_ref(value).iter(),
+            TypeSet::Multiple(values) => values.iter(),
+        }
+    }
+}
+
+/// A JSON schema, used for named definitions as well as every schema nested
+/// inside them (properties, array items, compositions, ...).
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Definition {
     description: Option<String>,
     #[serde(rename = "type")]
-    type_field: Option<DefinitionPropertyType>,
+    type_field: Option<TypeSet<DefinitionPropertyType>>,
+    /// Only `binary`, the OpenAPI 3 way of declaring file contents, changes
+    /// the lowered type.
+    format: Option<String>,
     #[serde(rename = "$ref")]
     _ref: Option<String>,
-    items: Option<KV>,
+    pub properties: Option<DefinitionPropertyMap>,
+    items: Option<Box<Definition>>,
     #[serde(rename = "additionalProperties")]
-    additional_properties: Option<KV>,
-}
-
-fn encode_kv_to_ts_object(kv: &KV) -> String {
-    let mut res = String::new();
-
-    kv.iter()
-        .for_each(|(key, value)| match *value == "integer".to_string() {
-            true => res.push_str(format!("{key}:{};", "number").as_str()),
-            false => res.push_str(format!("{key}:{};", clear_ref(value)).as_str()),
-        });
-
-    return res;
+    additional_properties: Option<AdditionalProperties>,
+    #[serde(rename = "prefixItems")]
+    prefix_items: Option<Vec<Definition>>,
+    #[serde(rename = "enum")]
+    pub _enum: Option<Vec<serde_json::Value>>,
+    #[serde(rename = "const")]
+    _const: Option<serde_json::Value>,
+    #[serde(rename = "$defs", default)]
+    defs: DefinitionMap,
+    #[serde(rename = "allOf")]
+    all_of: Option<Vec<Definition>>,
+    #[serde(rename = "oneOf")]
+    one_of: Option<Vec<Definition>>,
+    #[serde(rename = "anyOf")]
+    any_of: Option<Vec<Definition>>,
+    discriminator: Option<Discriminator>,
+    #[serde(default)]
+    required: Vec<String>,
+    #[serde(flatten)]
+    nullable: Nullable,
 }
 
-pub fn normalize_key(key: &String) -> String {
-    key.replace(".", "_")
-}
+impl Definition {
+    /// Moves nested `$defs` out of the definition so they can be emitted as
+    /// top-level types.
+    fn take_defs(&mut self) -> DefinitionMap {
+        let mut defs = std::mem::take(&mut self.defs);
 
-pub fn clear_ref(ref_string: &String) -> String {
-    normalize_key(ref_string).replace("#/definitions/", "")
-}
+        let nested: Vec<(String, Definition)> =
+            defs.values_mut().flat_map(|def| def.take_defs()).collect();
+        defs.extend(nested);
 
-pub fn parse_enum(props: &Definition) -> String {
-    match &props._enum {
-        Some(values) => values
-            .iter()
-            .map(|v| format!("'{}'", v).to_string())
-            .collect::<Vec<String>>()
-            .join(" | "),
-        None => "".to_string(),
+        defs
     }
-}
 
-pub fn create_raw_type_from_properties(props: &DefinitionPropertyMap) -> String {
-    let tokens: Vec<String> = props
-        .iter()
-        .map(|(key, value)| {
-            //println!("{} -> {:?}", key, value);
-
-            let mut inner = String::new();
-
-            inner.push_str(format!("{}:", key).as_str());
-
-            if value.type_field.is_some() {
-                match value.type_field.clone().unwrap() {
-                    DefinitionPropertyType::String => inner.push_str("string;"),
-                    DefinitionPropertyType::Integer => inner.push_str("number;"),
-                    DefinitionPropertyType::Number => inner.push_str("number;"),
-                    DefinitionPropertyType::Boolean => inner.push_str("boolean;"),
-                    DefinitionPropertyType::Array => {
-                        if value._ref.is_some() {
-                            inner.push_str(format!("{}[];", value._ref.clone().unwrap()).as_str())
-                        }
+    fn is_nullable(&self) -> bool {
+        self.nullable.is_set()
+            || self.type_field.as_ref().is_some_and(|types| {
+                types
+                    .iter()
+                    .any(|t| matches!(t, DefinitionPropertyType::Null))
+            })
+    }
 
-                        if value.items.is_some() {
-                            let items = value.items.clone().unwrap();
+    pub fn lower(&self) -> ir::Type {
+        let ty = self.lower_inner();
 
-                            if items.contains_key("$ref") {
-                                inner.push_str(
-                                    format!("{}[];", clear_ref(items.get("$ref").unwrap()))
-                                        .as_str(),
-                                );
-                            } else {
-                                let encoded = encode_kv_to_ts_object(&items);
+        match self.nullable.is_set() {
+            true => ty.nullable(),
+            false => ty,
+        }
+    }
 
-                                inner.push_str(format!("{{{}}}[];", encoded).as_str());
-                            }
-                        }
+    fn lower_inner(&self) -> ir::Type {
+        if let Some(constant) = &self._const {
+            return ir::Type::Literal(constant.clone());
+        }
 
-                        if value._ref.is_none() && value.items.is_none() {
-                            warn!("Array type without ref or items");
-                        }
-                    }
-                    DefinitionPropertyType::Object => {
-                        if value._ref.is_some() {
-                            inner.push_str(format!("{};", value._ref.clone().unwrap()).as_str());
-                        }
+        if let Some(values) = &self._enum {
+            return ir::Type::Enum(values.clone());
+        }
 
-                        if value.items.is_some() {
-                            let encoded = encode_kv_to_ts_object(&value.items.clone().unwrap());
+        if let Some(prefix_items) = &self.prefix_items {
+            return ir::Type::Tuple {
+                items: prefix_items.iter().map(Definition::lower).collect(),
+                rest: self.items.as_ref().map(|rest| Box::new(rest.lower())),
+            };
+        }
 
-                            inner.push_str(format!("{{{encoded}}};").as_str());
-                        }
+        if let Some(composition) = self.lower_composition() {
+            return match &self.properties {
+                Some(_) => ir::Type::intersection(vec![
+                    ir::Type::Object(ir::ObjectType {
+                        properties: self.lower_properties(),
+                        additional: None,
+                    }),
+                    composition,
+                ]),
+                None => composition,
+            };
+        }
 
-                        if value.additional_properties.is_some() {
-                            let encoded = encode_kv_to_ts_object(
-                                &value.additional_properties.clone().unwrap(),
-                            );
+        match &self.type_field {
+            Some(types) => {
+                let (nulls, rest): (Vec<_>, Vec<_>) = types
+                    .iter()
+                    .partition(|t| matches!(t, DefinitionPropertyType::Null));
 
-                            inner.push_str(format!("{{{encoded}}};").as_str());
-                        }
+                let mut members: Vec<ir::Type> =
+                    rest.into_iter().map(|t| self.lower_single(t)).collect();
 
-                        if value._ref.is_none()
-                            && value.items.is_none()
-                            && value.additional_properties.is_none()
-                        {
-                            warn!("Object type without ref, items, additional properties");
+                let ty = match members.len() {
+                    0 => ir::Type::Null,
+                    1 => members.pop().unwrap(),
+                    _ => ir::Type::Union(members),
+                };
 
-                            inner.push_str("never;");
-                        }
-                    }
+                match nulls.is_empty() {
+                    true => ty,
+                    false => ty.nullable(),
                 }
+            }
+            None => match (&self._ref, self.lower_object(), &self.items) {
+                (Some(_ref), _, _) => ir::Type::Ref(clear_ref(_ref)),
+                (None, Some(object), _) => ir::Type::Object(object),
+                (None, None, Some(items)) => ir::Type::Array(Box::new(items.lower())),
+                (None, None, None) => ir::Type::Any,
+            },
+        }
+    }
 
-                return inner;
+    fn lower_single(&self, type_field: &DefinitionPropertyType) -> ir::Type {
+        match type_field {
+            DefinitionPropertyType::Array => match (&self._ref, &self.items) {
+                (Some(_ref), _) => ir::Type::Array(Box::new(ir::Type::Ref(clear_ref(_ref)))),
+                (None, Some(items)) => ir::Type::Array(Box::new(items.lower())),
+                (None, None) => {
+                    warn!("Array type without ref or items");
+                    ir::Type::Array(Box::new(ir::Type::Any))
+                }
+            },
+            // Objects have always accepted their shape in `items` too.
+            DefinitionPropertyType::Object => {
+                match (&self._ref, self.lower_object(), &self.items) {
+                    (Some(_ref), _, _) => ir::Type::Ref(clear_ref(_ref)),
+                    (None, Some(object), _) => ir::Type::Object(object),
+                    (None, None, Some(items)) => items.lower(),
+                    (None, None, None) => ir::Type::Object(ir::ObjectType::default()),
+                }
             }
+            DefinitionPropertyType::String => match self.format.as_deref() {
+                Some("binary") => ir::Type::File,
+                _ => ir::Type::String,
+            },
+            DefinitionPropertyType::Integer => ir::Type::Integer,
+            DefinitionPropertyType::Number => ir::Type::Number,
+            DefinitionPropertyType::Boolean => ir::Type::Boolean,
+            DefinitionPropertyType::Null => ir::Type::Null,
+            DefinitionPropertyType::File => ir::Type::File,
+        }
+    }
 
-            if value._ref.is_some() {
-                inner.push_str(clear_ref(&value._ref.clone().unwrap()).as_str());
-                inner.push_str(";");
+    /// `allOf` becomes an intersection, `oneOf` and `anyOf` become unions. When
+    /// a schema combines several keywords, all of them have to hold.
+    fn lower_composition(&self) -> Option<ir::Type> {
+        let variant = |member: &Definition| match (&self.discriminator, &member._ref) {
+            (Some(discriminator), Some(_ref)) => ir::Type::Variant {
+                property: discriminator.property_name().to_string(),
+                value: discriminator.value_for(_ref),
+                ty: Box::new(ir::Type::Ref(clear_ref(_ref))),
+            },
+            _ => member.lower(),
+        };
 
-                return inner;
-            }
+        let mut parts: Vec<ir::Type> = Vec::new();
 
-            inner.push_str("never;");
+        if let Some(members) = &self.one_of {
+            parts.push(ir::Type::Union(members.iter().map(variant).collect()));
+        }
 
-            return inner;
-        })
-        .collect::<_>();
+        if let Some(members) = &self.any_of {
+            parts.push(ir::Type::Union(members.iter().map(variant).collect()));
+        }
 
-    let mut res = String::new();
-    res.push_str("{");
+        if let Some(members) = &self.all_of {
+            parts.push(ir::Type::intersection(
+                members.iter().map(Definition::lower).collect(),
+            ));
+        }
 
-    tokens.iter().for_each(|t| {
-        res.push_str(t.as_str());
-    });
+        match parts.len() {
+            0 => None,
+            1 => parts.pop(),
+            _ => Some(ir::Type::intersection(parts)),
+        }
+    }
 
-    res.push_str("}");
+    /// Declared properties and `additionalProperties`, `None` when the schema
+    /// has neither.
+    fn lower_object(&self) -> Option<ir::ObjectType> {
+        let additional = match &self.additional_properties {
+            Some(AdditionalProperties::Allowed(true)) => Some(ir::Type::Any),
+            Some(AdditionalProperties::Schema(schema)) => Some(schema.lower()),
+            Some(AdditionalProperties::Allowed(false)) if self.properties.is_none() => {
+                Some(ir::Type::Never)
+            }
+            Some(AdditionalProperties::Allowed(false)) | None => None,
+        };
 
-    return res;
-}
+        if self.properties.is_none() && additional.is_none() {
+            return None;
+        }
+
+        Some(ir::ObjectType {
+            properties: self.lower_properties(),
+            additional: additional.map(Box::new),
+        })
+    }
+
+    fn lower_properties(&self) -> Vec<ir::Property> {
+        self.properties
+            .iter()
+            .flatten()
+            .map(|(key, value)| ir::Property {
+                name: key.clone(),
+                description: value.description.clone(),
+                required: self.required.contains(key),
+                ty: value.lower(),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub enum DefinitionPropertyType {
+    #[serde(rename = "string")]
+    String,
+    #[serde(rename = "array")]
+    Array,
+    #[serde(rename = "integer")]
+    Integer,
+    #[serde(rename = "number")]
+    Number,
+    #[serde(rename = "boolean")]
+    Boolean,
+    #[serde(rename = "object")]
+    Object,
+    #[serde(rename = "null")]
+    Null,
+    /// Swagger 2 file uploads, only valid for `formData` parameters and
+    /// responses.
+    #[serde(rename = "file")]
+    File,
+}
+
+/// Swagger 2 names the discriminator property directly, OpenAPI 3 uses an
+/// object with an optional value -> schema mapping.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(untagged)]
+pub enum Discriminator {
+    Name(String),
+    Object {
+        #[serde(rename = "propertyName")]
+        property_name: String,
+        #[serde(default)]
+        mapping: HashMap<String, String>,
+    },
+}
+
+impl Discriminator {
+    fn property_name(&self) -> &str {
+        match self {
+            Discriminator::Name(name) => name,
+            Discriminator::Object { property_name, .. } => property_name,
+        }
+    }
+
+    /// The discriminator value for the schema behind `reference`, which
+    /// defaults to the schema name when the mapping doesn't mention it.
+    fn value_for(&self, reference: &str) -> String {
+        let name = clear_ref(reference);
+
+        match self {
+            Discriminator::Object { mapping, .. } => {
+                let mut entries: Vec<(&String, &String)> = mapping.iter().collect();
+                entries.sort();
+
+                entries
+                    .into_iter()
+                    .find(|(_, target)| *target == reference || clear_ref(target) == name)
+                    .map(|(value, _)| value.clone())
+                    .unwrap_or(name)
+            }
+            Discriminator::Name(_) => name,
+        }
+    }
+}
+
+/// Turns inheritance-style discriminators (a base schema that subtypes extend
+/// through `allOf`) into a union of the subtypes. The base object is kept as
+/// `<name>_base` so the subtypes can still extend it.
+fn apply_discriminators(definitions: &mut DefinitionMap) {
+    let mut bases: Vec<String> = definitions
+        .iter()
+        .filter(|(_, def)| {
+            def.discriminator.is_some() && def.one_of.is_none() && def.any_of.is_none()
+        })
+        .map(|(key, _)| key.clone())
+        .collect();
+    bases.sort();
+
+    bases.into_iter().for_each(|key| {
+        let base_key = format!("{}_base", key);
+
+        let extends_base = |member: &Definition| {
+            member
+                ._ref
+                .as_ref()
+                .is_some_and(|_ref| clear_ref(_ref) == key)
+        };
+
+        let mut subtypes: Vec<String> = definitions
+            .iter()
+            .filter(|(_, def)| def.all_of.iter().flatten().any(extends_base))
+            .map(|(subtype, _)| subtype.clone())
+            .collect();
+        subtypes.sort();
+
+        if subtypes.is_empty() {
+            warn!("Discriminator on {} has no subtypes", key);
+            return;
+        }
+
+        if definitions.contains_key(&base_key) {
+            warn!("Unable to split {}: {} already exists", key, base_key);
+            return;
+        }
+
+        subtypes.iter().for_each(|subtype| {
+            definitions
+                .get_mut(subtype)
+                .and_then(|def| def.all_of.as_mut())
+                .into_iter()
+                .flatten()
+                .filter(|member| extends_base(member))
+                .for_each(|member| member._ref = Some(format!("#/definitions/{}", base_key)));
+        });
+
+        let discriminator = definitions
+            .get_mut(&key)
+            .and_then(|base| base.discriminator.take());
+        let union = Definition {
+            one_of: Some(
+                subtypes
+                    .iter()
+                    .map(|subtype| Definition {
+                        _ref: Some(format!("#/definitions/{}", subtype)),
+                        ..Default::default()
+                    })
+                    .collect(),
+            ),
+            discriminator,
+            ..Default::default()
+        };
+
+        // Keeps the union where the base was declared.
+        let base = definitions.insert(key, union).unwrap_or_default();
+        definitions.insert(base_key, base);
+    });
+}
+
+pub fn clear_ref(ref_string: &str) -> String {
+    let name = match ref_string.rfind("/$defs/") {
+        Some(idx) => &ref_string[idx + "/$defs/".len()..],
+        None => ref_string
+            .trim_start_matches("#/definitions/")
+            .trim_start_matches("#/components/schemas/"),
+    };
+
+    name.replace("~1", "/").replace("~0", "~")
+}
 
 // create test block
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::options::Options;
+    use crate::template::{
+        parse_body, parse_definition, parse_formats, parse_params, parse_response,
+    };
+
+    fn parse(data: &str) -> Result<ir::Api> {
+        let source = Url::parse("https://api.example.com/docs/openapi.json").unwrap();
+
+        parse_schema(serde_json::from_str(data).unwrap(), &source)
+    }
+
+    fn operation<'a>(api: &'a ir::Api, path: &str, method: ir::Method) -> &'a ir::Operation {
+        api.operations
+            .iter()
+            .find(|op| op.path == path && op.method == method)
+            .unwrap()
+    }
+
+    fn response<'a>(op: &'a ir::Operation, status: &str) -> &'a ir::Response {
+        op.responses
+            .iter()
+            .find(|res| res.status == status)
+            .unwrap()
+    }
+
+    fn create_required_type(props: &DefinitionPropertyMap) -> String {
+        let definition = Definition {
+            type_field: Some(TypeSet::Single(DefinitionPropertyType::Object)),
+            properties: Some(props.clone()),
+            required: props.keys().cloned().collect(),
+            ..Default::default()
+        };
+
+        parse_definition(&definition.lower(), &Options::default())
+    }
 
     #[test]
     fn create_basic_schema() {
         let schema = Schema {
+            swagger: Some("2.0".to_string()),
+            openapi: None,
             schemes: vec!["https".to_string()],
             host: "api.example.com".to_string(),
             base_path: "/v1".to_string(),
-            paths: HashMap::new(),
-            definitions: HashMap::new(),
+            servers: Vec::new(),
+            consumes: Vec::new(),
+            produces: Vec::new(),
+            paths: IndexMap::new(),
+            definitions: IndexMap::new(),
+            components: None,
+            webhooks: IndexMap::new(),
         };
 
-        assert!(schema.schemes.len() > 0);
+        assert!(!schema.schemes.is_empty());
+        assert_eq!(schema.base_url(), "https://api.example.com/v1");
+    }
+
+    #[test]
+    fn resolve_relative_servers() {
+        let spec = |servers: &str| {
+            format!(r#"{{ "openapi": "3.0.0", "servers": {servers}, "paths": {{}} }}"#)
+        };
+
+        let api = parse(&spec(r#"[{ "url": "/api/" }]"#)).unwrap();
+        assert_eq!(api.base_url, "https://api.example.com/api");
+
+        let api = parse(&spec("[]")).unwrap();
+        assert_eq!(api.base_url, "https://api.example.com");
+
+        let file = Url::parse("file:///specs/openapi.json").unwrap();
+        let document = serde_json::from_str(&spec(r#"[{ "url": "/api/" }]"#)).unwrap();
+        assert_eq!(parse_schema(document, &file).unwrap().base_url, "/api");
+    }
+
+    #[test]
+    fn parse_openapi_3_document() {
+        let data = r##"{
+            "openapi": "3.0.3",
+            "servers": [{ "url": "https://{region}.example.com/v2/", "variables": { "region": { "default": "eu" } } }],
+            "paths": {
+                "/pets": {
+                    "post": {
+                        "requestBody": {
+                            "required": true,
+                            "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Pet" } } }
+                        },
+                        "parameters": [{ "name": "limit", "in": "query", "schema": { "type": "integer" } }],
+                        "responses": {
+                            "201": { "description": "Created", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Pet" } } } }
+                        }
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "Pet": { "type": "object", "properties": { "name": { "type": "string" } } }
+                }
+            }
+        }"##;
+
+        let api = parse(data).unwrap();
+        assert_eq!(api.base_url, "https://eu.example.com/v2");
+        assert!(api.types.contains_key("Pet"));
+
+        let op = operation(&api, "/pets", ir::Method::Post);
+        assert_eq!(
+            parse_params(op, ir::ParameterLocation::Query, &Options::default()),
+            "limit: number | undefined"
+        );
+
+        let body = op.body.as_ref().unwrap();
+        assert_eq!(parse_body(body, &Options::default()), "Pet");
+        assert_eq!(
+            parse_response(response(op, "201"), &Options::default()),
+            "Promise<Pet>"
+        );
+    }
+
+    #[test]
+    fn reject_unsupported_openapi_version() {
+        let data = r#"{ "openapi": "4.0.0", "paths": {} }"#;
+
+        assert!(parse(data).is_err());
     }
 
     #[test]
     fn parse_basic_object_definition() {
-        let mut properties: DefinitionPropertyMap = HashMap::new();
+        let mut properties: DefinitionPropertyMap = IndexMap::new();
         properties.insert(
             "blocks".into(),
-            DefinitionProperty {
-                description: None,
-                type_field: Some(DefinitionPropertyType::Integer),
-                _ref: None,
-                items: None,
-                additional_properties: None,
+            Definition {
+                type_field: Some(TypeSet::Single(DefinitionPropertyType::Integer)),
+                ..Default::default()
             },
         );
 
-        let response = create_raw_type_from_properties(&properties);
+        let response = create_required_type(&properties);
         let expected = "{blocks:number;}";
 
         assert_eq!(response, expected);
@@ -443,130 +1314,125 @@ mod test {
 
     #[test]
     fn parse_multiple_properties_object_definition() {
-        let mut properties: DefinitionPropertyMap = HashMap::new();
+        let mut properties: DefinitionPropertyMap = IndexMap::new();
         properties.insert(
             "blocks".into(),
-            DefinitionProperty {
-                description: None,
-                type_field: Some(DefinitionPropertyType::Integer),
-                _ref: None,
-                items: None,
-                additional_properties: None,
+            Definition {
+                type_field: Some(TypeSet::Single(DefinitionPropertyType::Integer)),
+                ..Default::default()
             },
         );
         properties.insert(
             "some".into(),
-            DefinitionProperty {
-                description: None,
-                type_field: Some(DefinitionPropertyType::String),
-                _ref: None,
-                items: None,
-                additional_properties: None,
+            Definition {
+                type_field: Some(TypeSet::Single(DefinitionPropertyType::String)),
+                ..Default::default()
             },
         );
 
-        let response = create_raw_type_from_properties(&properties);
+        let response = create_required_type(&properties);
 
         assert!(response.contains("some:string;"));
         assert!(response.contains("blocks:number;"));
+        assert_eq!(response, "{blocks:number;some:string;}");
     }
 
     #[test]
     fn parse_all_possible_properties_object_definition() {
-        let mut properties: DefinitionPropertyMap = HashMap::new();
+        let mut properties: DefinitionPropertyMap = IndexMap::new();
         properties.insert(
             "some1".into(),
-            DefinitionProperty {
-                description: None,
-                type_field: Some(DefinitionPropertyType::Integer),
-                _ref: None,
-                items: None,
-                additional_properties: None,
+            Definition {
+                type_field: Some(TypeSet::Single(DefinitionPropertyType::Integer)),
+                ..Default::default()
             },
         );
         properties.insert(
             "some2".into(),
-            DefinitionProperty {
-                description: None,
-                type_field: Some(DefinitionPropertyType::String),
-                _ref: None,
-                items: None,
-                additional_properties: None,
+            Definition {
+                type_field: Some(TypeSet::Single(DefinitionPropertyType::String)),
+                ..Default::default()
             },
         );
         properties.insert(
             "some3".into(),
-            DefinitionProperty {
-                description: None,
-                type_field: Some(DefinitionPropertyType::Boolean),
-                _ref: None,
-                items: None,
-                additional_properties: None,
+            Definition {
+                type_field: Some(TypeSet::Single(DefinitionPropertyType::Boolean)),
+                ..Default::default()
             },
         );
         properties.insert(
             "some4".into(),
-            DefinitionProperty {
-                description: None,
-                type_field: Some(DefinitionPropertyType::Array),
+            Definition {
+                type_field: Some(TypeSet::Single(DefinitionPropertyType::Array)),
                 _ref: Some("ref_type".to_string()),
                 items: None,
-                additional_properties: None,
+                ..Default::default()
             },
         );
 
-        let some_5_items: KV = [("some".to_string(), "string".to_string())]
-            .iter()
-            .cloned()
-            .collect();
+        let mut some_5_items = Definition {
+            type_field: Some(TypeSet::Single(DefinitionPropertyType::Object)),
+            required: vec!["some".to_string()],
+            ..Default::default()
+        };
+        some_5_items.properties = Some(IndexMap::from([(
+            "some".to_string(),
+            Definition {
+                type_field: Some(TypeSet::Single(DefinitionPropertyType::String)),
+                ..Default::default()
+            },
+        )]));
         properties.insert(
             "some5".into(),
-            DefinitionProperty {
-                description: None,
-                type_field: Some(DefinitionPropertyType::Array),
+            Definition {
+                type_field: Some(TypeSet::Single(DefinitionPropertyType::Array)),
                 _ref: None,
-                items: Some(some_5_items),
-                additional_properties: None,
+                items: Some(Box::new(some_5_items)),
+                ..Default::default()
             },
         );
 
         properties.insert(
             "some6".into(),
-            DefinitionProperty {
-                description: None,
-                type_field: Some(DefinitionPropertyType::Object),
+            Definition {
+                type_field: Some(TypeSet::Single(DefinitionPropertyType::Object)),
                 _ref: Some("ref_type".to_string()),
                 items: None,
-                additional_properties: None,
+                ..Default::default()
             },
         );
 
-        let some_7_items: KV = [("some".to_string(), "string".to_string())]
-            .iter()
-            .cloned()
-            .collect();
+        let mut some_7_items = Definition {
+            type_field: Some(TypeSet::Single(DefinitionPropertyType::Object)),
+            required: vec!["some".to_string()],
+            ..Default::default()
+        };
+        some_7_items.properties = Some(IndexMap::from([(
+            "some".to_string(),
+            Definition {
+                type_field: Some(TypeSet::Single(DefinitionPropertyType::String)),
+                ..Default::default()
+            },
+        )]));
         properties.insert(
             "some7".into(),
-            DefinitionProperty {
-                description: None,
-                type_field: Some(DefinitionPropertyType::Object),
+            Definition {
+                type_field: Some(TypeSet::Single(DefinitionPropertyType::Object)),
                 _ref: None,
-                items: Some(some_7_items),
-                additional_properties: None,
+                items: Some(Box::new(some_7_items)),
+                ..Default::default()
             },
         );
         properties.insert(
             "some8".into(),
-            DefinitionProperty {
-                description: None,
-                type_field: Some(DefinitionPropertyType::Integer),
-                _ref: None,
-                items: None,
-                additional_properties: None,
+            Definition {
+                type_field: Some(TypeSet::Single(DefinitionPropertyType::Integer)),
+                ..Default::default()
             },
         );
 
-        let response = create_raw_type_from_properties(&properties);
+        let response = create_required_type(&properties);
 
         assert!(response.contains("some1:number;"));
         assert!(response.contains("some2:string;"));
@@ -580,39 +1446,811 @@ mod test {
 
     #[test]
     fn parse_property_with_only_ref() {
-        let mut properties: DefinitionPropertyMap = HashMap::new();
+        let mut properties: DefinitionPropertyMap = IndexMap::new();
         properties.insert(
             "some".into(),
-            DefinitionProperty {
-                description: None,
+            Definition {
                 type_field: None,
                 _ref: Some("ref_type".to_string()),
                 items: None,
-                additional_properties: None,
+                ..Default::default()
             },
         );
 
-        let response = create_raw_type_from_properties(&properties);
+        let response = create_required_type(&properties);
 
         assert!(response.contains("some:ref_type;"));
     }
 
     #[test]
     fn parse_property_without_nothign() {
-        let mut properties: DefinitionPropertyMap = HashMap::new();
-        properties.insert(
-            "some".into(),
-            DefinitionProperty {
-                description: None,
-                type_field: None,
-                _ref: None,
-                items: None,
-                additional_properties: None,
+        let mut properties: DefinitionPropertyMap = IndexMap::new();
+        properties.insert("some".into(), Definition::default());
+
+        let response = create_required_type(&properties);
+
+        assert!(response.contains("some:never;"));
+    }
+
+    #[test]
+    fn parse_webhooks_only_document() {
+        let data = r##"{
+            "openapi": "3.1.0",
+            "servers": [{ "url": "https://api.example.com" }],
+            "webhooks": {
+                "ping": { "post": { "responses": { "200": { "description": "OK" } } } }
+            }
+        }"##;
+
+        let api = parse(data).unwrap();
+        assert!(api.operations.is_empty());
+        assert!(api.webhooks.contains_key("ping"));
+    }
+
+    #[test]
+    fn parse_openapi_3_1_document() {
+        let data = r##"{
+            "openapi": "3.1.0",
+            "paths": {},
+            "webhooks": {
+                "newPet": {
+                    "post": {
+                        "requestBody": {
+                            "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Pet" } } }
+                        },
+                        "responses": { "200": { "description": "OK" } }
+                    }
+                }
             },
+            "components": {
+                "schemas": {
+                    "Pet": {
+                        "type": ["object", "null"],
+                        "required": ["kind", "tag"],
+                        "properties": {
+                            "kind": { "const": "pet", "examples": ["pet"] },
+                            "tag": { "type": ["string", "null"] },
+                            "point": { "type": "array", "prefixItems": [{ "type": "number" }, { "type": "string" }] },
+                            "owner": { "$ref": "#/components/schemas/Pet/$defs/Owner" }
+                        },
+                        "$defs": {
+                            "Owner": { "type": "string", "examples": ["alice"] }
+                        }
+                    }
+                }
+            }
+        }"##;
+
+        let api = parse(data).unwrap();
+        assert!(api.webhooks.contains_key("newPet"));
+        assert_eq!(
+            parse_definition(api.types.get("Owner").unwrap(), &Options::default()),
+            "string"
         );
 
-        let response = create_raw_type_from_properties(&properties);
+        let pet = parse_definition(api.types.get("Pet").unwrap(), &Options::default());
+        assert!(pet.ends_with("} | null"));
+        assert!(pet.contains("kind:'pet';"));
+        assert!(pet.contains("tag:string | null;"));
+        assert!(pet.contains("point?:[number, string];"));
+        assert!(pet.contains("owner?:Owner;"));
+    }
 
-        assert!(response.contains("some:never;"));
+    #[test]
+    fn parse_enum_with_mixed_literals() {
+        let definition: Definition =
+            serde_json::from_str(r#"{ "enum": ["a", 1, true, null] }"#).unwrap();
+
+        assert_eq!(
+            parse_definition(&definition.lower(), &Options::default()),
+            "'a' | 1 | true | null"
+        );
+    }
+
+    #[test]
+    fn parse_composed_definitions() {
+        let definitions: DefinitionMap = serde_json::from_str(
+            r##"{
+                "Dog": {
+                    "allOf": [
+                        { "$ref": "#/definitions/Pet" },
+                        { "type": "object", "properties": { "bark": { "type": "boolean" } } }
+                    ]
+                },
+                "Animal": { "oneOf": [{ "$ref": "#/definitions/Dog" }, { "$ref": "#/definitions/Cat" }] },
+                "Tagged": {
+                    "type": "object",
+                    "properties": { "tag": { "type": "string" } },
+                    "anyOf": [{ "type": "string" }, { "type": "integer" }]
+                },
+                "Nested": {
+                    "allOf": [
+                        { "$ref": "#/definitions/Base" },
+                        { "anyOf": [{ "$ref": "#/definitions/A" }, { "$ref": "#/definitions/B" }] }
+                    ]
+                },
+                "Described": { "allOf": [{ "$ref": "#/definitions/Owner" }, { "description": "Owner" }] },
+                "MaybeOwner": { "allOf": [{ "$ref": "#/definitions/Owner" }, { "nullable": true }] }
+            }"##,
+        )
+        .unwrap();
+
+        let parse = |key: &str| {
+            parse_definition(&definitions.get(key).unwrap().lower(), &Options::default())
+        };
+
+        assert_eq!(parse("Dog"), "Pet & {bark?:boolean;}");
+        assert_eq!(parse("Animal"), "Dog | Cat");
+        assert_eq!(parse("Tagged"), "{tag?:string;} & (string | number)");
+        assert_eq!(parse("Nested"), "Base & (A | B)");
+        assert_eq!(parse("Described"), "Owner");
+        assert_eq!(parse("MaybeOwner"), "Owner | null");
+    }
+
+    #[test]
+    fn parse_discriminated_one_of() {
+        let definition: Definition = serde_json::from_str(
+            r##"{
+                "oneOf": [{ "$ref": "#/components/schemas/Dog" }, { "$ref": "#/components/schemas/Cat" }],
+                "discriminator": {
+                    "propertyName": "petType",
+                    "mapping": { "dog": "#/components/schemas/Dog" }
+                }
+            }"##,
+        )
+        .unwrap();
+
+        assert_eq!(
+            parse_definition(&definition.lower(), &Options::default()),
+            "Dog & {petType:'dog';} | Cat & {petType:'Cat';}"
+        );
+    }
+
+    #[test]
+    fn parse_discriminated_inheritance() {
+        let api = parse(
+            r##"{
+                "swagger": "2.0",
+                "paths": {},
+                "definitions": {
+                    "Pet": {
+                        "type": "object",
+                        "discriminator": "petType",
+                        "required": ["petType"],
+                        "properties": { "petType": { "type": "string" } }
+                    },
+                    "Dog": {
+                        "allOf": [
+                            { "$ref": "#/definitions/Pet" },
+                            { "type": "object", "properties": { "bark": { "type": "boolean" } } }
+                        ]
+                    },
+                    "Cat": { "allOf": [{ "$ref": "#/definitions/Pet" }] }
+                }
+            }"##,
+        )
+        .unwrap();
+
+        let parse_key =
+            |key: &str| parse_definition(api.types.get(key).unwrap(), &Options::default());
+
+        assert_eq!(
+            parse_key("Pet"),
+            "Cat & {petType:'Cat';} | Dog & {petType:'Dog';}"
+        );
+        assert_eq!(parse_key("Pet_base"), "{petType:string;}");
+        assert_eq!(parse_key("Dog"), "Pet_base & {bark?:boolean;}");
+    }
+
+    #[test]
+    fn parse_optional_properties() {
+        let definition: Definition = serde_json::from_str(
+            r#"{
+                "type": "object",
+                "required": ["id"],
+                "properties": {
+                    "id": { "type": "string" },
+                    "nested": {
+                        "type": "object",
+                        "required": ["inner"],
+                        "properties": { "inner": { "type": "integer" } }
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let default = parse_definition(&definition.lower(), &Options::default());
+        assert!(default.contains("id:string;"));
+        assert!(default.contains("nested?:{inner:number;};"));
+
+        let exact = parse_definition(
+            &definition.lower(),
+            &Options {
+                exact_optional_property_types: true,
+                ..Default::default()
+            },
+        );
+        assert!(exact.contains("id:string;"));
+        assert!(exact.contains("nested?:{inner:number;} | undefined;"));
+    }
+
+    #[test]
+    fn parse_nullable_schemas() {
+        let api = parse(
+            r##"{
+                "openapi": "3.0.0",
+                "paths": {
+                    "/pets/{id}": {
+                        "put": {
+                            "parameters": [
+                                { "name": "id", "in": "path", "required": true, "schema": { "type": "string", "nullable": true } }
+                            ],
+                            "requestBody": {
+                                "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Pet", "nullable": true } } }
+                            },
+                            "responses": {
+                                "200": { "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Pet", "type": ["object", "null"] } } } }
+                            }
+                        }
+                    }
+                },
+                "components": {
+                    "schemas": {
+                        "Pet": {
+                            "type": "object",
+                            "x-nullable": true,
+                            "required": ["name", "owner"],
+                            "properties": {
+                                "name": { "type": "string", "nullable": true },
+                                "owner": { "$ref": "#/components/schemas/Owner", "x-nullable": true },
+                                "tags": { "type": ["array", "null"], "items": { "$ref": "#/components/schemas/Tag" }, "nullable": true }
+                            }
+                        }
+                    }
+                }
+            }"##,
+        )
+        .unwrap();
+
+        let pet = parse_definition(api.types.get("Pet").unwrap(), &Options::default());
+        assert!(pet.ends_with("} | null"));
+        assert!(pet.contains("name:string | null;"));
+        assert!(pet.contains("owner:Owner | null;"));
+        assert!(pet.contains("tags?:Tag[] | null;"));
+
+        let op = operation(&api, "/pets/{id}", ir::Method::Put);
+        assert_eq!(
+            parse_params(op, ir::ParameterLocation::Path, &Options::default()),
+            "id: string | null"
+        );
+        assert_eq!(
+            parse_response(response(op, "200"), &Options::default()),
+            "Promise<Pet | null>"
+        );
+        assert_eq!(
+            parse_body(op.body.as_ref().unwrap(), &Options::default()),
+            "Pet | null"
+        );
+    }
+
+    #[test]
+    fn parse_nested_inline_schemas() {
+        let api = parse(
+            r##"{
+                "openapi": "3.0.0",
+                "paths": {
+                    "/grids": {
+                        "get": {
+                            "responses": {
+                                "200": { "content": { "application/json": { "schema": { "type": "array", "items": { "$ref": "#/components/schemas/Grid" } } } } }
+                            }
+                        }
+                    }
+                },
+                "components": {
+                    "schemas": {
+                        "Grid": {
+                            "type": "object",
+                            "required": ["cells", "owner"],
+                            "properties": {
+                                "cells": { "type": "array", "items": { "type": "array", "items": { "type": "integer" } } },
+                                "owner": {
+                                    "type": "object",
+                                    "required": ["name"],
+                                    "properties": {
+                                        "name": { "type": "string" },
+                                        "tags": { "type": "array", "items": { "type": "object", "properties": { "label": { "type": "string" } } } }
+                                    }
+                                },
+                                "values": { "type": "array", "items": { "oneOf": [{ "type": "string" }, { "type": "number" }] } }
+                            }
+                        },
+                        "Grids": { "type": "array", "items": { "$ref": "#/components/schemas/Grid" } }
+                    }
+                }
+            }"##,
+        )
+        .unwrap();
+
+        let grid = parse_definition(api.types.get("Grid").unwrap(), &Options::default());
+        assert!(grid.contains("cells:number[][];"));
+        assert!(grid.contains("owner:{"));
+        assert!(grid.contains("name:string;"));
+        assert!(grid.contains("tags?:{label?:string;}[];"));
+        assert!(grid.contains("values?:(string | number)[];"));
+
+        assert_eq!(
+            parse_definition(api.types.get("Grids").unwrap(), &Options::default()),
+            "Grid[]"
+        );
+
+        let op = operation(&api, "/grids", ir::Method::Get);
+        assert_eq!(
+            parse_response(response(op, "200"), &Options::default()),
+            "Promise<Grid[]>"
+        );
+    }
+
+    #[test]
+    fn parse_additional_properties() {
+        let api = parse(
+            r##"{
+                "swagger": "2.0",
+                "paths": {},
+                "definitions": {
+                    "Labels": { "type": "object", "additionalProperties": { "type": "string" } },
+                    "Metadata": { "type": "object", "additionalProperties": true },
+                    "Resource": {
+                        "type": "object",
+                        "required": ["name"],
+                        "properties": {
+                            "name": { "type": "string" },
+                            "labels": { "$ref": "#/definitions/Labels" },
+                            "extra": { "type": "object", "additionalProperties": {} },
+                            "owners": { "type": "object", "additionalProperties": { "$ref": "#/definitions/Owner" } },
+                            "limits": { "type": "object", "additionalProperties": { "type": "object", "properties": { "max": { "type": "integer" } } } },
+                            "closed": { "type": "object", "additionalProperties": false }
+                        },
+                        "additionalProperties": { "type": "string" }
+                    }
+                }
+            }"##,
+        )
+        .unwrap();
+
+        let render = |key: &str| parse_definition(api.types.get(key).unwrap(), &Options::default());
+
+        assert_eq!(render("Labels"), "Record<string, string>");
+        assert_eq!(render("Metadata"), "Record<string, unknown>");
+
+        let resource = render("Resource");
+        assert!(resource.starts_with("{"));
+        assert!(resource.ends_with("} & Record<string, string>"));
+        assert!(resource.contains("name:string;"));
+        assert!(resource.contains("labels?:Labels;"));
+        assert!(resource.contains("extra?:Record<string, unknown>;"));
+        assert!(resource.contains("owners?:Record<string, Owner>;"));
+        assert!(resource.contains("limits?:Record<string, {max?:number;}>;"));
+        assert!(resource.contains("closed?:Record<string, never>;"));
+    }
+
+    #[test]
+    fn parse_header_cookie_and_form_data_parameters() {
+        let api = parse(
+            r##"{
+                "swagger": "2.0",
+                "paths": {
+                    "/uploads": {
+                        "post": {
+                            "parameters": [
+                                { "name": "X-Request-Id", "in": "header", "type": "string", "required": true },
+                                { "name": "session", "in": "cookie", "type": "string", "required": true },
+                                { "name": "file", "in": "formData", "type": "file", "required": true },
+                                { "name": "comment", "in": "formData", "type": "string" }
+                            ],
+                            "responses": {}
+                        }
+                    }
+                }
+            }"##,
+        )
+        .unwrap();
+
+        let op = operation(&api, "/uploads", ir::Method::Post);
+        assert_eq!(
+            parse_params(op, ir::ParameterLocation::Header, &Options::default()),
+            "'X-Request-Id': string"
+        );
+        assert_eq!(
+            parse_params(op, ir::ParameterLocation::Cookie, &Options::default()),
+            "session: string"
+        );
+        assert_eq!(op.parameters.len(), 2);
+
+        let body = op.body.as_ref().unwrap();
+        assert_eq!(body.encoding, ir::BodyEncoding::Multipart);
+
+        let body = parse_body(body, &Options::default());
+        assert!(body.contains("file:Blob;"));
+        assert!(body.contains("comment?:string;"));
+    }
+
+    #[test]
+    fn parse_openapi_3_file_uploads() {
+        let api = parse(
+            r##"{
+                "openapi": "3.0.0",
+                "paths": {
+                    "/uploads": {
+                        "post": {
+                            "requestBody": {
+                                "content": {
+                                    "multipart/form-data": {
+                                        "schema": {
+                                            "type": "object",
+                                            "required": ["file"],
+                                            "properties": {
+                                                "file": { "type": "string", "format": "binary" },
+                                                "checksum": { "type": "string", "format": "byte" }
+                                            }
+                                        }
+                                    }
+                                }
+                            },
+                            "responses": {}
+                        }
+                    }
+                }
+            }"##,
+        )
+        .unwrap();
+
+        let body = operation(&api, "/uploads", ir::Method::Post)
+            .body
+            .as_ref()
+            .unwrap();
+        assert_eq!(body.encoding, ir::BodyEncoding::Multipart);
+
+        let body = parse_body(body, &Options::default());
+        assert!(body.contains("file:Blob;"));
+        assert!(body.contains("checksum?:string;"));
+    }
+
+    #[test]
+    fn detect_url_encoded_request_bodies() {
+        let api = parse(
+            r##"{
+                "openapi": "3.0.0",
+                "paths": {
+                    "/login": {
+                        "post": {
+                            "requestBody": {
+                                "content": {
+                                    "application/x-www-form-urlencoded": {
+                                        "schema": { "type": "object", "properties": { "user": { "type": "string" } } }
+                                    }
+                                }
+                            },
+                            "responses": {}
+                        }
+                    }
+                }
+            }"##,
+        )
+        .unwrap();
+
+        let op = operation(&api, "/login", ir::Method::Post);
+        assert_eq!(
+            op.body.as_ref().unwrap().encoding,
+            ir::BodyEncoding::UrlEncoded
+        );
+    }
+
+    #[test]
+    fn detect_response_encodings() {
+        let api = parse(
+            r##"{
+                "swagger": "2.0",
+                "produces": ["application/json"],
+                "paths": {
+                    "/exec": {
+                        "post": {
+                            "produces": ["application/octet-stream", "application/json"],
+                            "responses": {
+                                "200": { "description": "", "schema": { "type": "string" } },
+                                "400": { "description": "", "schema": { "type": "object" } }
+                            }
+                        }
+                    },
+                    "/pets": {
+                        "get": {
+                            "responses": { "200": { "description": "", "schema": { "type": "string" } } }
+                        }
+                    }
+                }
+            }"##,
+        )
+        .unwrap();
+
+        let exec = operation(&api, "/exec", ir::Method::Post);
+        assert_eq!(response(exec, "200").encoding, ir::ResponseEncoding::Binary);
+        assert_eq!(response(exec, "400").encoding, ir::ResponseEncoding::Json);
+        let pets = operation(&api, "/pets", ir::Method::Get);
+        assert_eq!(response(pets, "200").encoding, ir::ResponseEncoding::Json);
+
+        let api = parse(
+            r##"{
+                "openapi": "3.0.0",
+                "paths": {
+                    "/report": {
+                        "get": {
+                            "responses": {
+                                "200": { "description": "", "content": { "text/csv; charset=utf-8": { "schema": { "type": "string" } } } },
+                                "201": { "description": "", "content": { "application/problem+json": { "schema": { "type": "object" } } } }
+                            }
+                        }
+                    }
+                }
+            }"##,
+        )
+        .unwrap();
+
+        let report = operation(&api, "/report", ir::Method::Get);
+        assert_eq!(response(report, "200").encoding, ir::ResponseEncoding::Text);
+        assert_eq!(response(report, "201").encoding, ir::ResponseEncoding::Json);
+    }
+
+    #[test]
+    fn send_url_encoded_files_as_multipart() {
+        let api = parse(
+            r##"{
+                "openapi": "3.0.0",
+                "paths": {
+                    "/avatars": {
+                        "post": {
+                            "requestBody": {
+                                "content": {
+                                    "application/x-www-form-urlencoded": {
+                                        "schema": { "$ref": "#/components/schemas/Avatar" }
+                                    }
+                                }
+                            },
+                            "responses": {}
+                        }
+                    }
+                },
+                "components": {
+                    "schemas": {
+                        "Avatar": {
+                            "type": "object",
+                            "properties": { "image": { "type": "string", "format": "binary" } }
+                        }
+                    }
+                }
+            }"##,
+        )
+        .unwrap();
+
+        let op = operation(&api, "/avatars", ir::Method::Post);
+        assert_eq!(
+            op.body.as_ref().unwrap().encoding,
+            ir::BodyEncoding::Multipart
+        );
+    }
+
+    #[test]
+    fn parse_array_and_object_parameters() {
+        let api = parse(
+            r##"{
+                "swagger": "2.0",
+                "paths": {
+                    "/machines": {
+                        "get": {
+                            "parameters": [
+                                { "name": "ids", "in": "query", "type": "array", "items": { "type": "integer" }, "collectionFormat": "multi", "required": true },
+                                { "name": "tags", "in": "query", "type": "array", "items": { "type": "string", "enum": ["a", "b"] } },
+                                { "name": "ratio", "in": "query", "type": "number", "required": true }
+                            ],
+                            "responses": {}
+                        }
+                    }
+                }
+            }"##,
+        )
+        .unwrap();
+
+        let op = operation(&api, "/machines", ir::Method::Get);
+        assert_eq!(
+            parse_params(op, ir::ParameterLocation::Query, &Options::default()),
+            "ids: number[], tags: ('a' | 'b')[] | undefined, ratio: number"
+        );
+        assert_eq!(
+            parse_formats(op),
+            vec![("ids".to_string(), "multi"), ("tags".to_string(), "csv")]
+        );
+
+        let api = parse(
+            r##"{
+                "openapi": "3.0.0",
+                "paths": {
+                    "/machines/{ids}": {
+                        "get": {
+                            "parameters": [
+                                { "name": "ids", "in": "path", "required": true, "schema": { "type": "array", "items": { "type": "string" } } },
+                                { "name": "filter", "in": "query", "required": true, "style": "deepObject", "schema": { "type": "object", "properties": { "state": { "type": "string" } } } },
+                                { "name": "region", "in": "query", "required": true, "explode": false, "schema": { "type": "array", "items": { "type": "string" } } },
+                                { "name": "sort", "in": "query", "required": true, "schema": { "type": "array", "items": { "type": "string" } } }
+                            ],
+                            "responses": {}
+                        }
+                    }
+                }
+            }"##,
+        )
+        .unwrap();
+
+        let op = operation(&api, "/machines/{ids}", ir::Method::Get);
+        assert_eq!(
+            parse_params(op, ir::ParameterLocation::Query, &Options::default()),
+            "filter: {state?:string;}, region: string[], sort: string[]"
+        );
+        assert_eq!(
+            parse_formats(op),
+            vec![
+                ("ids".to_string(), "csv"),
+                ("filter".to_string(), "deepObject"),
+                ("region".to_string(), "csv"),
+                ("sort".to_string(), "multi"),
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_path_level_parameters() {
+        let api = parse(
+            r##"{
+                "swagger": "2.0",
+                "paths": {
+                    "/apps/{app_name}/machines": {
+                        "parameters": [
+                            { "name": "app_name", "in": "path", "type": "string", "required": true },
+                            { "name": "limit", "in": "query", "type": "integer" }
+                        ],
+                        "get": {
+                            "parameters": [
+                                { "name": "limit", "in": "query", "type": "integer", "required": true }
+                            ],
+                            "responses": {}
+                        },
+                        "post": { "responses": {} }
+                    }
+                }
+            }"##,
+        )
+        .unwrap();
+
+        let path = "/apps/{app_name}/machines";
+        let params = |method, location| {
+            parse_params(operation(&api, path, method), location, &Options::default())
+        };
+
+        assert_eq!(
+            params(ir::Method::Get, ir::ParameterLocation::Path),
+            "app_name: string"
+        );
+        assert_eq!(
+            params(ir::Method::Get, ir::ParameterLocation::Query),
+            "limit: number"
+        );
+        assert_eq!(
+            params(ir::Method::Post, ir::ParameterLocation::Path),
+            "app_name: string"
+        );
+        assert_eq!(
+            params(ir::Method::Post, ir::ParameterLocation::Query),
+            "limit: number | undefined"
+        );
+    }
+
+    #[test]
+    fn parse_head_options_and_trace_operations() {
+        let api = parse(
+            r##"{
+                "openapi": "3.0.0",
+                "paths": {
+                    "/health": {
+                        "parameters": [{ "name": "deep", "in": "query", "schema": { "type": "boolean" } }],
+                        "head": { "responses": { "200": { "description": "OK" } } },
+                        "options": { "responses": { "204": { "description": "Preflight" } } },
+                        "trace": { "responses": { "200": { "description": "Echo" } } }
+                    }
+                }
+            }"##,
+        )
+        .unwrap();
+
+        assert_eq!(
+            api.operations
+                .iter()
+                .map(|op| op.method)
+                .collect::<Vec<_>>(),
+            vec![ir::Method::Head, ir::Method::Options, ir::Method::Trace]
+        );
+        assert_eq!(
+            parse_params(
+                operation(&api, "/health", ir::Method::Head),
+                ir::ParameterLocation::Query,
+                &Options::default()
+            ),
+            "deep: boolean | undefined"
+        );
+    }
+
+    #[test]
+    fn sanitize_spec_names() {
+        let api = parse(
+            r##"{
+                "swagger": "2.0",
+                "paths": {},
+                "definitions": {
+                    "default": { "type": "string", "enum": ["it's", "ok"] },
+                    "Foo Bar@v1": {
+                        "type": "object",
+                        "required": ["x-id"],
+                        "properties": {
+                            "x-id": { "type": "string" },
+                            "kind": { "$ref": "#/definitions/default" }
+                        }
+                    }
+                }
+            }"##,
+        )
+        .unwrap();
+
+        assert_eq!(
+            parse_definition(api.types.get("default").unwrap(), &Options::default()),
+            r"'it\'s' | 'ok'"
+        );
+
+        let foo = parse_definition(api.types.get("Foo Bar@v1").unwrap(), &Options::default());
+        assert!(foo.contains("'x-id':string;"));
+        assert!(foo.contains("kind?:default_;"));
+    }
+
+    #[test]
+    fn preserve_spec_order() {
+        let api = parse(
+            r##"{
+                "swagger": "2.0",
+                "paths": {},
+                "definitions": {
+                    "Zebra": {
+                        "type": "object",
+                        "properties": {
+                            "stripes": { "type": "integer" },
+                            "age": { "type": "integer" }
+                        }
+                    },
+                    "Ant": { "type": "string" }
+                }
+            }"##,
+        )
+        .unwrap();
+        let zebra = api.types.get("Zebra").unwrap();
+        let options = Options {
+            preserve_spec_order: true,
+            ..Default::default()
+        };
+
+        assert_eq!(api.types.keys().collect::<Vec<_>>(), vec!["Zebra", "Ant"]);
+        assert_eq!(
+            parse_definition(zebra, &options),
+            "{stripes?:number;age?:number;}"
+        );
+        assert_eq!(
+            parse_definition(zebra, &Options::default()),
+            "{age?:number;stripes?:number;}"
+        );
     }
 }
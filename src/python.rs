@@ -0,0 +1,1278 @@
+use anyhow::Result;
+use log::warn;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use crate::generator::{ordered, ordered_operations, ordered_properties, Generator, OutputFile};
+use crate::ir;
+use crate::naming::NamingStyle;
+use crate::options::Options;
+use crate::sanitizer;
+
+/// Names imported or declared by the generated module.
+const RUNTIME_TYPES: &[&str] = &[
+    "Annotated",
+    "Any",
+    "ApiError",
+    "AsyncClient",
+    "BaseModel",
+    "Client",
+    "ConfigDict",
+    "Dict",
+    "Field",
+    "List",
+    "Literal",
+    "Optional",
+    "RootModel",
+    "Tuple",
+    "TypeAdapter",
+    "Union",
+];
+/// Methods of the generated clients.
+const CLIENT_METHODS: &[&str] = &["aclose", "close"];
+
+const KEYWORDS: &[&str] = &[
+    "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif",
+    "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda",
+    "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with", "yield",
+];
+/// Field names that would shadow `BaseModel` attributes or the builtins used
+/// in annotations.
+const SHADOWED: &[&str] = &[
+    "bool",
+    "bytes",
+    "construct",
+    "copy",
+    "dict",
+    "fields",
+    "float",
+    "from_orm",
+    "int",
+    "json",
+    "parse_file",
+    "parse_obj",
+    "parse_raw",
+    "schema",
+    "schema_json",
+    "str",
+    "update_forward_refs",
+    "validate",
+];
+
+/// A single module with Pydantic models and `httpx` based clients, `Client`
+/// and `AsyncClient`, with one method per operation.
+pub struct Python;
+
+impl Generator for Python {
+    fn generate(&self, api: ir::Api, options: &Options, path: &Path) -> Result<Vec<OutputFile>> {
+        let mut models = Models::new(&api, options);
+        models.declare_types();
+
+        let mut names = sanitizer::Identifiers::new(CLIENT_METHODS);
+        let methods: Vec<Method> = ordered_operations(&api.operations, options)
+            .into_iter()
+            .map(|op| models.method(op, &mut names))
+            .collect();
+
+        Ok(vec![OutputFile {
+            path: path.to_path_buf(),
+            contents: generate_module(&api.base_url, &models, &methods),
+        }])
+    }
+}
+
+/// Client method of an operation, rendered once for `Client` and once for
+/// `AsyncClient`.
+struct Method {
+    name: String,
+    description: String,
+    http_method: &'static str,
+    /// f-string building the URL.
+    url: String,
+    /// Positional arguments, name and annotation.
+    required: Vec<(String, String)>,
+    /// Keyword arguments defaulting to `None`.
+    optional: Vec<(String, String)>,
+    /// Keyword arguments of the `httpx` request.
+    request: Vec<String>,
+    response: String,
+    response_encoding: ir::ResponseEncoding,
+}
+
+/// Named types of the API and the classes and aliases declared for them,
+/// inline objects and string enums are hoisted into classes of their own.
+struct Models<'a> {
+    api: &'a ir::Api,
+    options: &'a Options,
+    /// Spec name -> Python name of every named type.
+    names: HashMap<String, String>,
+    /// Named types declared as classes but nullable, referenced as
+    /// `Optional[T]`.
+    nullable: HashSet<String>,
+    recursive: HashSet<String>,
+    /// Named types whose properties are being merged into a model.
+    merging: HashSet<String>,
+    /// Python name -> discriminator property -> values of the discriminated
+    /// unions the model is a variant of.
+    tags: HashMap<String, BTreeMap<String, Vec<String>>>,
+    types: sanitizer::Identifiers,
+    items: Vec<String>,
+    /// Models to rebuild once every class is declared.
+    models: Vec<String>,
+    /// Set where types are evaluated when the module loads, names declared
+    /// later must be quoted there.
+    quoted: bool,
+}
+
+impl<'a> Models<'a> {
+    fn new(api: &'a ir::Api, options: &'a Options) -> Models<'a> {
+        let mut types = sanitizer::Identifiers::new(RUNTIME_TYPES);
+        let names: HashMap<String, String> = ordered(&api.types, options)
+            .into_iter()
+            .map(|(key, _)| {
+                let name = types.claim(&type_ident(key), &format!("schema {key}"));
+                (key.clone(), name)
+            })
+            .collect();
+        let tags = api
+            .variant_tags()
+            .into_iter()
+            .filter_map(|(key, tags)| Some((names.get(&key)?.clone(), tags)))
+            .collect();
+        let nullable = api
+            .types
+            .iter()
+            .filter(|(_, ty)| matches!(ty, ir::Type::Nullable(inner) if is_class(inner)))
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        Models {
+            api,
+            options,
+            names,
+            nullable,
+            recursive: api.recursive_types(),
+            merging: HashSet::new(),
+            tags,
+            types,
+            items: Vec::new(),
+            models: Vec::new(),
+            quoted: false,
+        }
+    }
+
+    fn declare_types(&mut self) {
+        let api = self.api;
+
+        ordered(&api.types, self.options)
+            .into_iter()
+            .for_each(|(key, _)| {
+                let name = self.names[key].clone();
+                let ty = match &api.types[key] {
+                    ir::Type::Nullable(inner) if self.nullable.contains(key) => inner.as_ref(),
+                    ty => ty,
+                };
+
+                if is_class(ty) {
+                    return self.class(&name, ty);
+                }
+
+                let index = self.items.len();
+                self.quoted = true;
+                let target = self.py_type(ty, &name);
+                self.quoted = false;
+
+                // Recursive aliases can't be expanded, they become root models.
+                let code = match self.recursive.contains(key) {
+                    true => {
+                        self.models.push(name.clone());
+                        format!("class {name}(RootModel[{target}]):\n    pass\n")
+                    }
+                    false => format!("{name} = {target}\n"),
+                };
+                self.items.insert(index, code);
+            });
+    }
+
+    /// Declares `name` for an object, string enum or intersection. Nested
+    /// classes are declared after it.
+    fn class(&mut self, name: &str, ty: &ir::Type) {
+        let quoted = std::mem::take(&mut self.quoted);
+        let index = self.items.len();
+
+        let code = match ty {
+            ir::Type::Object(object) => {
+                let properties = ordered_properties(&object.properties, self.options);
+                self.render_model(name, &properties, object.additional.as_deref())
+            }
+            ir::Type::Enum(values) => render_enum(name, values),
+            ir::Type::Intersection(members) => self.render_intersection(name, members),
+            _ => unreachable!("{name} is not declared as a class"),
+        };
+
+        self.items.insert(index, code);
+        self.quoted = quoted;
+    }
+
+    /// Declares an inline type under a name derived from where it is used.
+    fn hoist(&mut self, hint: &str, ty: &ir::Type) -> String {
+        let name = self
+            .types
+            .claim(&type_ident(hint), &format!("inline schema {hint}"));
+        self.class(&name, ty);
+
+        self.quote(name)
+    }
+
+    fn quote(&self, name: String) -> String {
+        match self.quoted {
+            true => format!("\"{name}\""),
+            false => name,
+        }
+    }
+
+    /// Annotation of `ty`.
+    fn py_type(&mut self, ty: &ir::Type, hint: &str) -> String {
+        match ty {
+            ir::Type::Any | ir::Type::Never => "Any".to_string(),
+            ir::Type::Null => "None".to_string(),
+            ir::Type::Boolean => "bool".to_string(),
+            ir::Type::Integer => "int".to_string(),
+            ir::Type::Number => "float".to_string(),
+            ir::Type::String => "str".to_string(),
+            ir::Type::File => "bytes".to_string(),
+            ir::Type::Literal(value) => match value {
+                serde_json::Value::Null => "None".to_string(),
+                value => format!("Literal[{}]", literal(value)),
+            },
+            ir::Type::Enum(_) if is_class(ty) => self.hoist(hint, ty),
+            ir::Type::Enum(values) => {
+                let (nulls, values): (Vec<_>, Vec<_>) = values.iter().partition(|v| v.is_null());
+                let values: Vec<String> = values.into_iter().map(literal).collect();
+
+                let inner = match values.is_empty() {
+                    true => "None".to_string(),
+                    false => format!("Literal[{}]", values.join(", ")),
+                };
+
+                match nulls.is_empty() {
+                    true => inner,
+                    false => optional(inner),
+                }
+            }
+            ir::Type::Array(items) => {
+                format!("List[{}]", self.py_type(items, &format!("{hint}Item")))
+            }
+            ir::Type::Tuple { items, rest: None } => {
+                let items: Vec<String> = items
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, item)| self.py_type(item, &format!("{hint}{}", idx + 1)))
+                    .collect();
+
+                match items.is_empty() {
+                    true => "Tuple[()]".to_string(),
+                    false => format!("Tuple[{}]", items.join(", ")),
+                }
+            }
+            ir::Type::Tuple { rest: Some(_), .. } => "List[Any]".to_string(),
+            ir::Type::Object(object) if object.properties.is_empty() => {
+                let value = match object.additional.as_deref() {
+                    Some(ir::Type::Never) | None => "Any".to_string(),
+                    Some(ty) => self.py_type(ty, &format!("{hint}Value")),
+                };
+
+                format!("Dict[str, {value}]")
+            }
+            // Merging a type into its own properties would never end, the
+            // recursive intersection is referenced as that type instead.
+            ir::Type::Intersection(members) => match self.merged_member(members) {
+                Some(target) => self.reference(&target),
+                None => self.hoist(hint, ty),
+            },
+            ir::Type::Object(_) => self.hoist(hint, ty),
+            ir::Type::Union(members) => {
+                let members = members
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, member)| match member {
+                        ir::Type::Variant { value, ty, .. } => {
+                            self.py_type(ty, &format!("{hint}{}", type_ident(value)))
+                        }
+                        member => self.py_type(member, &format!("{hint}{}", idx + 1)),
+                    })
+                    .collect();
+
+                match self.discriminated(ty) {
+                    Some(property) => format!(
+                        "Annotated[{}, Field(discriminator={})]",
+                        union(members),
+                        string_literal(&field_ident(property))
+                    ),
+                    None => union(members),
+                }
+            }
+            ir::Type::Ref(name) => self.reference(name),
+            ir::Type::Variant { ty, .. } => self.py_type(ty, hint),
+            ir::Type::Nullable(inner) => optional(self.py_type(inner, hint)),
+        }
+    }
+
+    /// Discriminator property of a union whose variants are all models.
+    fn discriminated<'t>(&self, ty: &'t ir::Type) -> Option<&'t str> {
+        let ir::Type::Union(members) = ty else {
+            return None;
+        };
+
+        let models = members.iter().all(|member| match member {
+            ir::Type::Variant { ty, .. } => match ty.as_ref() {
+                ir::Type::Ref(target) if !self.nullable.contains(target) => {
+                    match self.api.types.get(target) {
+                        Some(ty @ (ir::Type::Object(_) | ir::Type::Intersection(_))) => {
+                            is_class(ty)
+                        }
+                        _ => false,
+                    }
+                }
+                _ => false,
+            },
+            _ => false,
+        });
+
+        match models && members.len() > 1 {
+            true => ty.discriminator(),
+            false => None,
+        }
+    }
+
+    fn reference(&self, name: &str) -> String {
+        let py_name = match self.names.get(name) {
+            Some(py_name) => self.quote(py_name.clone()),
+            None => {
+                warn!("Reference to unknown schema {}", name);
+                return "Any".to_string();
+            }
+        };
+
+        match self.nullable.contains(name) {
+            true => optional(py_name),
+            false => py_name,
+        }
+    }
+
+    /// A model with `properties`. Undeclared properties are kept unless
+    /// `additional` is `Never`.
+    fn render_model(
+        &mut self,
+        name: &str,
+        properties: &[&ir::Property],
+        additional: Option<&ir::Type>,
+    ) -> String {
+        self.models.push(name.to_string());
+
+        let mut taken = sanitizer::Identifiers::new(&[]);
+        let mut config = Vec::new();
+
+        // Variants of discriminated unions need their discriminator as a
+        // required literal, it's added when the schema doesn't declare it.
+        let tags = self.tags.get(name).cloned().unwrap_or_default();
+        let undeclared: Vec<ir::Property> = tags
+            .keys()
+            .filter(|tag| properties.iter().all(|prop| prop.name != **tag))
+            .map(|tag| ir::Property {
+                name: tag.clone(),
+                description: None,
+                required: true,
+                ty: ir::Type::String,
+            })
+            .collect();
+        let properties: Vec<&ir::Property> = properties
+            .iter()
+            .copied()
+            .chain(undeclared.iter())
+            .collect();
+
+        let fields: String = properties
+            .iter()
+            .map(|prop| {
+                let ident = taken.claim(
+                    &field_ident(&prop.name),
+                    &format!("property {} of {name}", prop.name),
+                );
+                let (ty, required) = match tags.get(&prop.name) {
+                    Some(values) => {
+                        let values: Vec<String> =
+                            values.iter().map(|value| string_literal(value)).collect();
+                        (format!("Literal[{}]", values.join(", ")), true)
+                    }
+                    None => (
+                        self.py_type(&prop.ty, &format!("{name}{}", type_ident(&prop.name))),
+                        prop.required,
+                    ),
+                };
+
+                let mut arguments = Vec::new();
+                let ty = match required {
+                    true => ty,
+                    false => {
+                        arguments.push("default=None".to_string());
+                        optional(ty)
+                    }
+                };
+                if ident != prop.name {
+                    arguments.push(format!("alias={}", string_literal(&prop.name)));
+                }
+                if let Some(description) = &prop.description {
+                    arguments.push(format!(
+                        "description={}",
+                        string_literal(description.trim())
+                    ));
+                }
+
+                match arguments.as_slice() {
+                    [] => format!("    {ident}: {ty}\n"),
+                    [default] if default == "default=None" => format!("    {ident}: {ty} = None\n"),
+                    _ => format!("    {ident}: {ty} = Field({})\n", arguments.join(", ")),
+                }
+            })
+            .collect();
+
+        if properties
+            .iter()
+            .any(|prop| field_ident(&prop.name) != prop.name)
+        {
+            config.push("populate_by_name=True".to_string());
+        }
+        match additional {
+            Some(ir::Type::Never) => config.push("extra=\"forbid\"".to_string()),
+            Some(_) => config.push("extra=\"allow\"".to_string()),
+            None => (),
+        }
+
+        let config = match config.is_empty() {
+            true => String::new(),
+            false => format!("    model_config = ConfigDict({})\n\n", config.join(", ")),
+        };
+        let body = match fields.is_empty() && config.is_empty() {
+            true => "    pass\n".to_string(),
+            false => format!("{config}{fields}"),
+        };
+
+        format!("class {name}(BaseModel):\n{body}")
+    }
+
+    /// Model with the properties of every member. Referenced objects are
+    /// merged too, so classes can be declared in any order.
+    fn render_intersection(&mut self, name: &str, members: &[ir::Type]) -> String {
+        let mut properties = Vec::new();
+        let mut additional = None;
+        let mut visited = HashSet::new();
+
+        self.merge_members(members, &mut properties, &mut additional, &mut visited);
+
+        let properties = ordered_properties(properties, self.options);
+
+        let merged: Vec<String> = visited
+            .into_iter()
+            .filter(|target| self.merging.insert(target.to_string()))
+            .map(str::to_string)
+            .collect();
+        let model = self.render_model(name, &properties, additional);
+        merged.iter().for_each(|target| {
+            self.merging.remove(target);
+        });
+
+        model
+    }
+
+    /// A referenced member of the intersection that is already being merged.
+    fn merged_member(&self, members: &[ir::Type]) -> Option<String> {
+        members.iter().find_map(|member| match member {
+            ir::Type::Ref(target) if self.merging.contains(target) => Some(target.clone()),
+            ir::Type::Variant { ty, .. } => self.merged_member(std::slice::from_ref(ty)),
+            ir::Type::Intersection(members) => self.merged_member(members),
+            _ => None,
+        })
+    }
+
+    fn merge_members<'t>(
+        &self,
+        members: &'t [ir::Type],
+        properties: &mut Vec<&'t ir::Property>,
+        additional: &mut Option<&'t ir::Type>,
+        visited: &mut HashSet<&'t str>,
+    ) where
+        'a: 't,
+    {
+        let api = self.api;
+
+        members.iter().for_each(|member| {
+            let member = match member {
+                ir::Type::Variant { ty, .. } => ty.as_ref(),
+                member => member,
+            };
+
+            let member = match member {
+                ir::Type::Ref(target) if !visited.insert(target.as_str()) => return,
+                ir::Type::Ref(target) => match api.types.get(target) {
+                    Some(ty) => ty,
+                    None => return warn!("Reference to unknown schema {}", target),
+                },
+                member => member,
+            };
+
+            match member {
+                ir::Type::Object(object) => {
+                    object.properties.iter().for_each(|prop| {
+                        match properties.iter().position(|p| p.name == prop.name) {
+                            Some(idx) => properties[idx] = prop,
+                            None => properties.push(prop),
+                        }
+                    });
+                    *additional = additional.or(object.additional.as_deref());
+                }
+                ir::Type::Intersection(members) => {
+                    self.merge_members(members, properties, additional, visited)
+                }
+                _ => warn!("Only objects can be merged into a model, skipping an allOf member"),
+            }
+        });
+    }
+
+    fn method(&mut self, op: &ir::Operation, names: &mut sanitizer::Identifiers) -> Method {
+        let base = match (self.options.use_operation_id, &op.operation_id) {
+            (true, Some(operation_id)) => operation_id.clone(),
+            _ => sanitizer::create_fn_name_from_path(op.method.as_str(), &op.path),
+        };
+        let name = names.claim(
+            &field_ident(&base),
+            &format!("{} {}", op.method.as_str(), op.path),
+        );
+        let hint = type_ident(&name);
+
+        let mut taken = sanitizer::Identifiers::new(&["self", "body", "response"]);
+        let mut required = Vec::new();
+        let mut optional_args = Vec::new();
+        let mut query = Vec::new();
+        let mut headers = Vec::new();
+        let mut cookies = Vec::new();
+        let mut path_args: HashMap<&str, String> = HashMap::new();
+
+        op.parameters.iter().for_each(|param| {
+            let ident = taken.claim(
+                &field_ident(&param.name),
+                &format!("parameter {} of {name}", param.name),
+            );
+            let ty = self.py_type(&param.ty, &format!("{hint}{}", type_ident(&param.name)));
+            let format = param.serialization.map_or("csv", |s| s.as_str());
+            let serialized = format!(
+                "*_serialize_param({}, {ident}, {})",
+                string_literal(&param.name),
+                string_literal(format)
+            );
+
+            match param.location {
+                ir::ParameterLocation::Path => {
+                    path_args.insert(&param.name, format!("_path_param({ident}, '{format}')"));
+                }
+                ir::ParameterLocation::Query => query.push(serialized),
+                ir::ParameterLocation::Header => headers.push(serialized),
+                ir::ParameterLocation::Cookie => cookies.push(serialized),
+            }
+
+            match (param.required, param.nullable) {
+                (true, false) => required.push((ident, ty)),
+                (true, true) => required.push((ident, optional(ty))),
+                (false, _) => optional_args.push((ident, optional(ty))),
+            }
+        });
+
+        if !cookies.is_empty() {
+            headers.push(format!("*_cookie_header([{}])", cookies.join(", ")));
+        }
+
+        let mut url = String::new();
+        op.path_parts().into_iter().for_each(|part| match part {
+            ir::PathPart::Placeholder(name) if path_args.contains_key(name) => {
+                url.push_str(&format!("{{{}}}", path_args[name]));
+            }
+            ir::PathPart::Placeholder(name) => {
+                warn!(
+                    "No path parameter {} declared for {} {}, keeping the placeholder",
+                    name,
+                    op.method.as_str(),
+                    op.path
+                );
+                url.push_str(&f_string_literal(&format!("{{{name}}}")));
+            }
+            ir::PathPart::Literal(text) => url.push_str(&f_string_literal(text)),
+        });
+
+        let mut request = Vec::new();
+        if !query.is_empty() {
+            request.push(format!("params=[{}]", query.join(", ")));
+        }
+        if !headers.is_empty() {
+            request.push(format!("headers=[{}]", headers.join(", ")));
+        }
+
+        if let Some(body) = &op.body {
+            let ty = self.py_type(&body.ty, &format!("{hint}Body"));
+            required.push(("body".to_string(), ty));
+
+            match body.encoding {
+                ir::BodyEncoding::Json => request.push("json=_dump(body)".to_string()),
+                ir::BodyEncoding::UrlEncoded => request.push("data=_fields(body)".to_string()),
+                ir::BodyEncoding::Multipart => request.push("files=_parts(body)".to_string()),
+            }
+        }
+
+        let mut responses: Vec<&ir::Type> = Vec::new();
+        op.responses_in(&["2"])
+            .filter_map(|res| res.ty.as_ref())
+            .for_each(|ty| {
+                if !responses.contains(&ty) {
+                    responses.push(ty);
+                }
+            });
+        let response_encoding = op.response_encoding();
+
+        let response = match (response_encoding, responses.as_slice()) {
+            (_, []) => "None".to_string(),
+            (ir::ResponseEncoding::Text, _) => "str".to_string(),
+            (ir::ResponseEncoding::Binary, _) => "bytes".to_string(),
+            (ir::ResponseEncoding::Json, [ty]) => self.py_type(ty, &format!("{hint}Response")),
+            (ir::ResponseEncoding::Json, _) => "Any".to_string(),
+        };
+
+        Method {
+            name,
+            description: op.description.clone(),
+            http_method: op.method.as_str(),
+            url: format!("f\"{{self._base_url}}{url}\""),
+            required,
+            optional: optional_args,
+            request,
+            response,
+            response_encoding,
+        }
+    }
+}
+
+/// Object or string enum, declared as a class of its own.
+fn is_class(ty: &ir::Type) -> bool {
+    match ty {
+        ir::Type::Object(object) => !object.properties.is_empty(),
+        ir::Type::Enum(values) => !values.is_empty() && values.iter().all(|v| v.is_string()),
+        ir::Type::Intersection(_) => true,
+        _ => false,
+    }
+}
+
+fn render_enum(name: &str, values: &[serde_json::Value]) -> String {
+    let mut taken = sanitizer::Identifiers::new(&[]);
+
+    let members: String = values
+        .iter()
+        .filter_map(serde_json::Value::as_str)
+        .map(|value| {
+            let member = taken.claim(&member_ident(value), &format!("value {value} of {name}"));
+            format!("    {member} = {}\n", string_literal(value))
+        })
+        .collect();
+
+    format!("class {name}(str, enum.Enum):\n{members}")
+}
+
+fn optional(ty: String) -> String {
+    match ty.as_str() {
+        "Any" | "None" => ty,
+        _ if ty.starts_with("Optional[") => ty,
+        _ => format!("Optional[{ty}]"),
+    }
+}
+
+/// Annotation accepting any of `members`, skipping duplicates.
+fn union(members: Vec<String>) -> String {
+    let mut nullable = false;
+    let members = members.into_iter().fold(Vec::new(), |mut acc, x| {
+        let x = match x
+            .strip_prefix("Optional[")
+            .and_then(|x| x.strip_suffix(']'))
+        {
+            Some(inner) => {
+                nullable = true;
+                inner.to_string()
+            }
+            None => x,
+        };
+
+        match x.as_str() {
+            "None" => nullable = true,
+            _ if !acc.contains(&x) => acc.push(x),
+            _ => (),
+        }
+        acc
+    });
+
+    let inner = match members.len() {
+        0 => "None".to_string(),
+        1 => members.into_iter().next().unwrap(),
+        _ => format!("Union[{}]", members.join(", ")),
+    };
+
+    match nullable {
+        true => optional(inner),
+        false => inner,
+    }
+}
+
+fn literal(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => "None".to_string(),
+        serde_json::Value::Bool(true) => "True".to_string(),
+        serde_json::Value::Bool(false) => "False".to_string(),
+        serde_json::Value::Number(number) => number.to_string(),
+        serde_json::Value::String(value) => string_literal(value),
+        _ => {
+            warn!("Unsupported literal value: {}", value);
+            "None".to_string()
+        }
+    }
+}
+
+/// `text` escaped for a double quoted f-string.
+fn f_string_literal(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('{', "{{")
+        .replace('}', "}}")
+}
+
+/// Double quoted Python string.
+fn string_literal(value: &str) -> String {
+    let escaped: String = value
+        .chars()
+        .map(|c| match c {
+            '\\' => "\\\\".to_string(),
+            '"' => "\\\"".to_string(),
+            '\n' => "\\n".to_string(),
+            '\r' => "\\r".to_string(),
+            '\t' => "\\t".to_string(),
+            c if c.is_control() => format!("\\u{:04x}", c as u32),
+            c => c.to_string(),
+        })
+        .collect();
+
+    format!("\"{escaped}\"")
+}
+
+fn type_ident(name: &str) -> String {
+    match NamingStyle::Pascal.apply(name).as_str() {
+        "" => "Model".to_string(),
+        ident @ ("None" | "True" | "False") => format!("{ident}_"),
+        ident => ident.to_string(),
+    }
+}
+
+fn member_ident(value: &str) -> String {
+    match NamingStyle::Snake.apply(value).to_uppercase() {
+        ident if ident.is_empty() => "EMPTY".to_string(),
+        ident if ident.starts_with('_') => format!("VALUE{ident}"),
+        ident => ident,
+    }
+}
+
+/// Snake case identifier. Pydantic ignores names starting with an underscore
+/// and reserves the `model_` prefix, such names are changed too.
+fn field_ident(name: &str) -> String {
+    let ident = match NamingStyle::Snake.apply(name) {
+        ident if ident.is_empty() => "value".to_string(),
+        ident if ident.starts_with('_') => format!("field{ident}"),
+        ident => ident,
+    };
+
+    match KEYWORDS.contains(&ident.as_str())
+        || SHADOWED.contains(&ident.as_str())
+        || ident.starts_with("model_")
+    {
+        true => format!("{ident}_"),
+        false => ident,
+    }
+}
+
+fn docstring(text: &str, indent: &str) -> String {
+    let text = text
+        .trim()
+        .replace('\\', "\\\\")
+        .replace("\"\"\"", "\\\"\\\"\\\"");
+
+    match text.lines().count() {
+        0 => String::new(),
+        1 => format!("{indent}\"\"\"{text}\"\"\"\n"),
+        _ => {
+            let lines: String = text
+                .lines()
+                .map(|line| match line.trim_end() {
+                    "" => "\n".to_string(),
+                    line => format!("{indent}{line}\n"),
+                })
+                .collect();
+
+            format!("{indent}\"\"\"\n{lines}{indent}\"\"\"\n")
+        }
+    }
+}
+
+fn generate_module(base_url: &str, models: &Models, methods: &[Method]) -> String {
+    let items = models.items.join("\n\n");
+    let rebuild: String = models
+        .models
+        .iter()
+        .map(|model| format!("{model}.model_rebuild()\n"))
+        .collect();
+
+    let render = |asynchronous| {
+        methods
+            .iter()
+            .map(|method| generate_method(method, asynchronous))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let (methods, async_methods) = (render(false), render(true));
+
+    format!(
+        r#""""API client generated by openapi-gen, do not edit."""
+
+from __future__ import annotations
+
+import enum
+import json
+from typing import Annotated, Any, Dict, List, Literal, Optional, Tuple, Union
+from urllib.parse import quote
+
+import httpx
+from pydantic import BaseModel, ConfigDict, Field, RootModel, TypeAdapter
+
+BASE_URL = {base_url}
+
+
+{items}
+
+{rebuild}
+
+class ApiError(Exception):
+    """The API answered with an error status."""
+
+    def __init__(self, response: httpx.Response) -> None:
+        super().__init__(f"the API answered {{response.status_code}}: {{response.text}}")
+        self.response = response
+
+
+def _dump(value: Any) -> Any:
+    # Fields never set are left out, explicit `None`s are sent as `null`.
+    return TypeAdapter(Any).dump_python(value, mode="json", by_alias=True, exclude_unset=True)
+
+
+def _scalar(value: Any) -> str:
+    return value if isinstance(value, str) else json.dumps(value)
+
+
+def _serialize_param(key: str, value: Any, style: str) -> List[Tuple[str, str]]:
+    """Name and value pairs of a parameter, arrays and objects serialized in
+    `style` (`csv`, `ssv`, `tsv`, `pipes`, `multi`, `form` or `deepObject`)."""
+    value = _dump(value)
+    delimiter = {{"ssv": " ", "tsv": "\t", "pipes": "|"}}.get(style, ",")
+
+    if value is None:
+        return []
+    if isinstance(value, list):
+        if style in ("multi", "form"):
+            return [(key, _scalar(item)) for item in value]
+        return [(key, delimiter.join(_scalar(item) for item in value))]
+    if isinstance(value, dict):
+        entries = [(prop, item) for prop, item in value.items() if item is not None]
+        if style == "deepObject":
+            return [(f"{{key}}[{{prop}}]", _scalar(item)) for prop, item in entries]
+        if style in ("form", "multi"):
+            return [(prop, _scalar(item)) for prop, item in entries]
+        return [(key, delimiter.join(part for prop, item in entries for part in (prop, _scalar(item))))]
+    return [(key, _scalar(value))]
+
+
+def _path_param(value: Any, style: str) -> str:
+    pairs = _serialize_param("", value, style)
+    return quote(pairs[0][1] if pairs else "", safe="")
+
+
+def _cookie_header(pairs: List[Tuple[str, str]]) -> List[Tuple[str, str]]:
+    if not pairs:
+        return []
+    return [("Cookie", "; ".join(f"{{name}}={{quote(value, safe='')}}" for name, value in pairs))]
+
+
+def _entries(body: Any) -> List[Tuple[str, Any]]:
+    if isinstance(body, BaseModel):
+        fields = [(field.alias or name, getattr(body, name)) for name, field in type(body).model_fields.items()]
+        return fields + list((body.model_extra or {{}}).items())
+    return list(dict(body).items())
+
+
+def _fields(body: Any) -> Dict[str, List[str]]:
+    """Form fields of `body`, files left out and objects sent as JSON under
+    their own name."""
+    fields: Dict[str, List[str]] = {{}}
+    for key, value in _entries(body):
+        if isinstance(value, bytes):
+            continue
+        value = _dump(value)
+        if isinstance(value, dict):
+            fields.setdefault(key, []).append(json.dumps(value))
+            continue
+        for name, item in _serialize_param(key, value, "multi"):
+            fields.setdefault(name, []).append(item)
+    return fields
+
+
+def _parts(body: Any) -> List[Tuple[str, Any]]:
+    """Parts of a multipart body. Fields are parts without a file name, which
+    keeps the body multipart when it holds no file."""
+    fields = [(name, (None, item)) for name, items in _fields(body).items() for item in items]
+    return fields + [(key, value) for key, value in _entries(body) if isinstance(value, bytes)]
+
+
+def _raise_for_status(response: httpx.Response) -> None:
+    if response.is_error:
+        raise ApiError(response)
+
+
+def _decode(response: httpx.Response, ty: Any) -> Any:
+    _raise_for_status(response)
+    # Empty bodies, e.g. `204 No Content`, decode as `null`.
+    return TypeAdapter(ty).validate_json(response.content or b"null")
+
+
+class Client:
+    def __init__(self, base_url: str = BASE_URL, client: Optional[httpx.Client] = None) -> None:
+        self._base_url = base_url
+        self._client = client or httpx.Client()
+
+    def close(self) -> None:
+        self._client.close()
+
+{methods}
+
+class AsyncClient:
+    def __init__(self, base_url: str = BASE_URL, client: Optional[httpx.AsyncClient] = None) -> None:
+        self._base_url = base_url
+        self._client = client or httpx.AsyncClient()
+
+    async def aclose(self) -> None:
+        await self._client.aclose()
+
+{async_methods}"#,
+        base_url = string_literal(base_url),
+    )
+}
+
+fn generate_method(method: &Method, asynchronous: bool) -> String {
+    let (asyncness, awaited) = match asynchronous {
+        true => ("async ", "await "),
+        false => ("", ""),
+    };
+
+    let mut arguments = vec!["self".to_string()];
+    arguments.extend(
+        method
+            .required
+            .iter()
+            .map(|(name, ty)| format!("{name}: {ty}")),
+    );
+    if !method.optional.is_empty() {
+        arguments.push("*".to_string());
+        arguments.extend(
+            method
+                .optional
+                .iter()
+                .map(|(name, ty)| format!("{name}: {ty} = None")),
+        );
+    }
+
+    let mut request = vec![string_literal(method.http_method), method.url.clone()];
+    request.extend(method.request.iter().cloned());
+
+    let result = match (method.response.as_str(), method.response_encoding) {
+        ("None", _) => "_raise_for_status(response)".to_string(),
+        (response, ir::ResponseEncoding::Json) => format!("return _decode(response, {response})"),
+        (_, ir::ResponseEncoding::Text) => {
+            "_raise_for_status(response)\n        return response.text".to_string()
+        }
+        (_, ir::ResponseEncoding::Binary) => {
+            "_raise_for_status(response)\n        return response.content".to_string()
+        }
+    };
+
+    let arguments = match arguments.as_slice() {
+        [argument] => argument.clone(),
+        _ => format!(
+            "\n{}    ",
+            arguments
+                .iter()
+                .map(|argument| format!("        {argument},\n"))
+                .collect::<String>()
+        ),
+    };
+
+    format!(
+        "    {asyncness}def {}({arguments}) -> {}:\n{}        response = {awaited}self._client.request(\n{}        )\n        {result}\n",
+        method.name,
+        method.response,
+        docstring(&method.description, "        "),
+        request
+            .iter()
+            .map(|argument| format!("            {argument},\n"))
+            .collect::<String>(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn declare_pydantic_models() {
+        let api = ir::Api {
+            types: indexmap::IndexMap::from([
+                (
+                    "pet".to_string(),
+                    ir::Type::Object(ir::ObjectType {
+                        properties: vec![
+                            ir::Property {
+                                name: "class".to_string(),
+                                description: Some("Kind of pet".to_string()),
+                                required: true,
+                                ty: ir::Type::Enum(vec!["cat".into(), "hot-dog".into()]),
+                            },
+                            ir::Property {
+                                name: "parent".to_string(),
+                                description: None,
+                                required: false,
+                                ty: ir::Type::Ref("pet".to_string()),
+                            },
+                        ],
+                        additional: None,
+                    }),
+                ),
+                (
+                    "Id".to_string(),
+                    ir::Type::Union(vec![ir::Type::Ref("pet".to_string()), ir::Type::Null]),
+                ),
+            ]),
+            ..Default::default()
+        };
+        let options = Options::default();
+
+        let mut models = Models::new(&api, &options);
+        models.declare_types();
+
+        assert_eq!(
+            models.items,
+            vec![
+                "Id = Optional[\"Pet\"]\n",
+                "class Pet(BaseModel):\n    model_config = ConfigDict(populate_by_name=True)\n\n    class_: PetClass = Field(alias=\"class\", description=\"Kind of pet\")\n    parent: Optional[Pet] = None\n",
+                "class PetClass(str, enum.Enum):\n    CAT = \"cat\"\n    HOT_DOG = \"hot-dog\"\n",
+            ]
+        );
+        assert_eq!(models.models, vec!["Pet"]);
+    }
+
+    #[test]
+    fn declare_self_referencing_intersections() {
+        let property = |name: &str, ty| ir::Property {
+            name: name.to_string(),
+            description: None,
+            required: false,
+            ty,
+        };
+        let pet = || ir::Type::Ref("Pet".to_string());
+        // `parent: { nullable: true, allOf: [Pet] }` and
+        // `owner: { allOf: [Pet, { since }] }`, as lowered by the parser.
+        let api = ir::Api {
+            types: indexmap::IndexMap::from([(
+                "Pet".to_string(),
+                ir::Type::Object(ir::ObjectType {
+                    properties: vec![
+                        property(
+                            "parent",
+                            ir::Type::intersection(vec![
+                                pet(),
+                                ir::Type::Nullable(Box::new(ir::Type::Any)),
+                            ]),
+                        ),
+                        property(
+                            "owner",
+                            ir::Type::intersection(vec![
+                                pet(),
+                                ir::Type::Object(ir::ObjectType {
+                                    properties: vec![property("since", ir::Type::String)],
+                                    additional: None,
+                                }),
+                            ]),
+                        ),
+                    ],
+                    additional: None,
+                }),
+            )]),
+            ..Default::default()
+        };
+        let options = Options::default();
+
+        let mut models = Models::new(&api, &options);
+        models.declare_types();
+
+        assert_eq!(
+            models.items,
+            vec![
+                "class Pet(BaseModel):\n    owner: Optional[PetOwner] = None\n    parent: Optional[Pet] = None\n",
+                "class PetOwner(BaseModel):\n    owner: Optional[Pet] = None\n    parent: Optional[Pet] = None\n    since: Optional[str] = None\n",
+            ]
+        );
+    }
+
+    #[test]
+    fn declare_discriminated_unions() {
+        let variant = |value: &str, target: &str| ir::Type::Variant {
+            property: "kind".to_string(),
+            value: value.to_string(),
+            ty: Box::new(ir::Type::Ref(target.to_string())),
+        };
+        let model = |properties| {
+            ir::Type::Object(ir::ObjectType {
+                properties,
+                additional: None,
+            })
+        };
+        let api = ir::Api {
+            types: indexmap::IndexMap::from([
+                (
+                    "Pet".to_string(),
+                    ir::Type::Union(vec![variant("cat", "Cat"), variant("dog", "Dog")]),
+                ),
+                (
+                    "Cat".to_string(),
+                    model(vec![ir::Property {
+                        name: "kind".to_string(),
+                        description: None,
+                        required: false,
+                        ty: ir::Type::String,
+                    }]),
+                ),
+                (
+                    "Dog".to_string(),
+                    model(vec![ir::Property {
+                        name: "barks".to_string(),
+                        description: None,
+                        required: true,
+                        ty: ir::Type::Boolean,
+                    }]),
+                ),
+            ]),
+            ..Default::default()
+        };
+        let options = Options {
+            preserve_spec_order: true,
+            ..Default::default()
+        };
+
+        let mut models = Models::new(&api, &options);
+        models.declare_types();
+
+        assert_eq!(
+            models.items,
+            vec![
+                "Pet = Annotated[Union[\"Cat\", \"Dog\"], Field(discriminator=\"kind\")]\n",
+                "class Cat(BaseModel):\n    kind: Literal[\"cat\"]\n",
+                "class Dog(BaseModel):\n    barks: bool\n    kind: Literal[\"dog\"]\n",
+            ]
+        );
+    }
+
+    #[test]
+    fn read_responses_by_media_type() {
+        let api = ir::Api::default();
+        let options = Options::default();
+        let operation = |encoding| ir::Operation {
+            method: ir::Method::Post,
+            path: "/exec".to_string(),
+            operation_id: None,
+            description: String::new(),
+            parameters: vec![],
+            body: None,
+            responses: vec![ir::Response {
+                status: "200".to_string(),
+                ty: Some(ir::Type::String),
+                encoding,
+            }],
+        };
+        let mut models = Models::new(&api, &options);
+        let mut method = |encoding| {
+            let mut names = sanitizer::Identifiers::new(&[]);
+            generate_method(&models.method(&operation(encoding), &mut names), false)
+        };
+
+        assert!(method(ir::ResponseEncoding::Json).contains("-> str:"));
+        assert!(method(ir::ResponseEncoding::Json).contains("return _decode(response, str)"));
+        assert!(method(ir::ResponseEncoding::Text).contains("return response.text"));
+        let bytes = method(ir::ResponseEncoding::Binary);
+        assert!(bytes.contains("-> bytes:"));
+        assert!(bytes.contains("_raise_for_status(response)\n        return response.content"));
+    }
+
+    #[test]
+    fn send_multipart_bodies_as_parts() {
+        let api = ir::Api::default();
+        let options = Options::default();
+        let operation = |encoding| ir::Operation {
+            method: ir::Method::Post,
+            path: "/uploads".to_string(),
+            operation_id: None,
+            description: String::new(),
+            parameters: vec![],
+            body: Some(ir::Body {
+                ty: ir::Type::Any,
+                encoding,
+            }),
+            responses: vec![],
+        };
+        let mut models = Models::new(&api, &options);
+        let mut method = |encoding| {
+            let mut names = sanitizer::Identifiers::new(&[]);
+            generate_method(&models.method(&operation(encoding), &mut names), false)
+        };
+
+        let multipart = method(ir::BodyEncoding::Multipart);
+        assert!(multipart.contains("files=_parts(body),"));
+        assert!(!multipart.contains("data="));
+        assert!(method(ir::BodyEncoding::UrlEncoded).contains("data=_fields(body),"));
+    }
+
+    #[test]
+    fn keep_undeclared_path_placeholders() {
+        let api = ir::Api::default();
+        let options = Options::default();
+        let op = ir::Operation {
+            method: ir::Method::Get,
+            path: "/pets/{id}".to_string(),
+            operation_id: None,
+            description: String::new(),
+            parameters: vec![],
+            body: None,
+            responses: vec![],
+        };
+
+        let mut models = Models::new(&api, &options);
+        let method = models.method(&op, &mut sanitizer::Identifiers::new(&[]));
+
+        assert_eq!(method.url, "f\"{self._base_url}/pets/{{id}}\"");
+    }
+}
@@ -0,0 +1,426 @@
+use anyhow::{anyhow, bail, Context, Result};
+use log::debug;
+use percent_encoding::percent_decode_str;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use url::Url;
+
+use crate::loader;
+use crate::sanitizer;
+
+/// Resolves every `$ref` in the document so the parser only ever sees refs to
+/// named schemas of the root document.
+///
+/// Refs to named schemas (`definitions`, `components/schemas`, `$defs`) are
+/// kept as type references; schemas living in other files or remote documents
+/// are hoisted into the root document under a unique name. Every other ref
+/// (parameters, responses, request bodies, path items, nested pointers) is
+/// inlined.
+pub fn resolve(document: Value, source: &Url) -> Result<Value> {
+    let mut resolver = Resolver::new(document.clone(), source);
+
+    let mut root = document;
+    resolver.walk(&mut root, source)?;
+    resolver.insert_hoisted(&mut root);
+
+    Ok(root)
+}
+
+struct Resolver {
+    root: Url,
+    /// Every loaded document, keyed by its URL without fragment.
+    documents: HashMap<Url, Value>,
+    /// External schema location -> name it was hoisted under.
+    hoisted: HashMap<String, String>,
+    hoisted_schemas: Vec<(String, Value)>,
+    taken_names: sanitizer::Identifiers,
+    /// Refs currently being inlined, used to detect cycles.
+    inlining: Vec<String>,
+    /// Keys leading to the value being walked.
+    path: Vec<String>,
+}
+
+enum Resolution {
+    Keep(String),
+    Inline(Value),
+}
+
+impl Resolver {
+    fn new(root_document: Value, source: &Url) -> Resolver {
+        let mut root = source.clone();
+        root.set_fragment(None);
+
+        let mut taken_names = sanitizer::Identifiers::new(&[]);
+        ["/definitions", "/components/schemas"]
+            .iter()
+            .filter_map(|pointer| root_document.pointer(pointer))
+            .filter_map(|value| value.as_object())
+            .flat_map(|map| map.keys())
+            .for_each(|name| taken_names.insert(name, &format!("schema {name}")));
+
+        Resolver {
+            documents: HashMap::from([(root.clone(), root_document)]),
+            root,
+            hoisted: HashMap::new(),
+            hoisted_schemas: Vec::new(),
+            taken_names,
+            inlining: Vec::new(),
+            path: Vec::new(),
+        }
+    }
+
+    fn is_openapi_3(&self) -> bool {
+        self.documents[&self.root].get("openapi").is_some()
+    }
+
+    fn definitions_pointer(&self) -> &'static str {
+        match self.is_openapi_3() {
+            true => "#/components/schemas/",
+            false => "#/definitions/",
+        }
+    }
+
+    fn walk(&mut self, value: &mut Value, base: &Url) -> Result<()> {
+        match value {
+            Value::Object(map) => {
+                let reference = map.get("$ref").and_then(|r| r.as_str()).map(String::from);
+
+                for (key, child) in map.iter_mut().filter(|(key, _)| *key != "$ref") {
+                    self.path.push(key.clone());
+                    let result = self.walk(child, base);
+                    self.path.pop();
+                    result?;
+                }
+
+                if let Some(reference) = reference {
+                    match self.resolve_ref(&reference, base)? {
+                        Resolution::Keep(reference) => {
+                            map.insert("$ref".to_string(), Value::String(reference));
+                        }
+                        Resolution::Inline(Value::Object(mut target)) => {
+                            map.shift_remove("$ref");
+                            target.append(map);
+                            *map = target;
+                        }
+                        Resolution::Inline(target) => *value = target,
+                    }
+                }
+            }
+            Value::Array(values) => {
+                for (idx, child) in values.iter_mut().enumerate() {
+                    self.path.push(idx.to_string());
+                    let result = self.walk(child, base);
+                    self.path.pop();
+                    result?;
+                }
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    fn resolve_ref(&mut self, reference: &str, base: &Url) -> Result<Resolution> {
+        let target = base
+            .join(reference)
+            .with_context(|| format!("Invalid $ref {}", reference))?;
+        let pointer = percent_decode_str(target.fragment().unwrap_or(""))
+            .decode_utf8()
+            .with_context(|| format!("Invalid $ref {}", reference))?
+            .to_string();
+
+        let mut document = target.clone();
+        document.set_fragment(None);
+
+        let name = schema_name(&pointer);
+
+        if document == self.root {
+            return match name {
+                Some(_) => Ok(Resolution::Keep(format!("#{}", pointer))),
+                None => self.inline(&target, &document, &pointer),
+            };
+        }
+
+        let name = match name {
+            Some(name) => Some(name),
+            None if pointer.is_empty() && self.in_schema() => Some(file_stem(&document)),
+            None => None,
+        };
+
+        match name {
+            Some(name) => {
+                let name = self.hoist(&target, &document, &pointer, name)?;
+
+                Ok(Resolution::Keep(format!(
+                    "{}{}",
+                    self.definitions_pointer(),
+                    name
+                )))
+            }
+            None => self.inline(&target, &document, &pointer),
+        }
+    }
+
+    fn inline(&mut self, target: &Url, document: &Url, pointer: &str) -> Result<Resolution> {
+        let key = target.to_string();
+
+        if self.inlining.contains(&key) {
+            let mut chain = self.inlining.clone();
+            chain.push(key);
+            bail!("Circular $ref detected: {}", chain.join(" -> "));
+        }
+
+        let mut value = self.lookup(document, pointer)?;
+
+        self.inlining.push(key);
+        let result = self.walk(&mut value, document);
+        self.inlining.pop();
+        result?;
+
+        Ok(Resolution::Inline(value))
+    }
+
+    fn hoist(
+        &mut self,
+        target: &Url,
+        document: &Url,
+        pointer: &str,
+        name: String,
+    ) -> Result<String> {
+        let key = target.to_string();
+
+        if let Some(name) = self.hoisted.get(&key) {
+            return Ok(name.clone());
+        }
+
+        let name = self.taken_names.claim(&name, &key);
+        debug!("Hoisting {} as {}", key, name);
+
+        // Registered before walking so cyclic schemas end up referencing the
+        // name instead of recursing forever.
+        self.hoisted.insert(key, name.clone());
+
+        let mut value = self.lookup(document, pointer)?;
+
+        let path = std::mem::take(&mut self.path);
+        let result = self.walk(&mut value, document);
+        self.path = path;
+        result?;
+
+        self.hoisted_schemas.push((name.clone(), value));
+
+        Ok(name)
+    }
+
+    fn lookup(&mut self, document: &Url, pointer: &str) -> Result<Value> {
+        if !self.documents.contains_key(document) {
+            debug!("Loading referenced document {}", document);
+
+            let loaded = loader::load(document)?;
+            self.documents.insert(document.clone(), loaded);
+        }
+
+        self.documents[document]
+            .pointer(pointer)
+            .cloned()
+            .ok_or_else(|| anyhow!("Unable to resolve $ref {}#{}", document, pointer))
+    }
+
+    /// Whether the value being walked sits where a schema is expected, used
+    /// to decide if a whole-file ref should become a named type.
+    fn in_schema(&self) -> bool {
+        let mut keys = self.path.iter().rev().map(String::as_str);
+
+        matches!(
+            (keys.next(), keys.next()),
+            (Some("schema" | "items" | "additionalProperties" | "not"), _)
+                | (
+                    Some(_),
+                    Some("properties" | "definitions" | "schemas" | "$defs")
+                )
+                | (Some(_), Some("allOf" | "oneOf" | "anyOf" | "prefixItems"))
+        )
+    }
+
+    fn insert_hoisted(&mut self, root: &mut Value) {
+        if self.hoisted_schemas.is_empty() {
+            return;
+        }
+
+        let keys: &[&str] = match self.is_openapi_3() {
+            true => &["components", "schemas"],
+            false => &["definitions"],
+        };
+
+        let target = keys.iter().fold(root, |acc, key| {
+            let map = acc.as_object_mut().expect("document root is an object");
+            map.entry(key.to_string())
+                .or_insert_with(|| Value::Object(Map::new()))
+        });
+
+        if let Some(map) = target.as_object_mut() {
+            map.extend(std::mem::take(&mut self.hoisted_schemas));
+        }
+    }
+}
+
+/// Returns the type name for pointers that address a named schema.
+fn schema_name(pointer: &str) -> Option<String> {
+    let segments: Vec<String> = pointer
+        .split('/')
+        .skip(1)
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+        .collect();
+
+    match segments.as_slice() {
+        [root, name] if root == "definitions" => Some(name.clone()),
+        [components, schemas, name] if components == "components" && schemas == "schemas" => {
+            Some(name.clone())
+        }
+        [.., defs, name] if defs == "$defs" => Some(name.clone()),
+        _ => None,
+    }
+}
+
+fn file_stem(document: &Url) -> String {
+    document
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .and_then(|file| file.split('.').next())
+        .filter(|stem| !stem.is_empty())
+        .unwrap_or("External")
+        .to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Directory of fixture files, removed once the test is done with it.
+    struct Fixtures(std::path::PathBuf);
+
+    impl std::ops::Deref for Fixtures {
+        type Target = std::path::Path;
+
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    impl Drop for Fixtures {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn write_files(name: &str, files: &[(&str, &str)]) -> Fixtures {
+        let dir = std::env::temp_dir().join(format!("openapi-gen-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        files.iter().for_each(|(file, contents)| {
+            std::fs::write(dir.join(file), contents).unwrap();
+        });
+
+        Fixtures(dir)
+    }
+
+    fn resolve_file(dir: &std::path::Path, file: &str) -> Result<Value> {
+        let url = loader::source_url(dir.join(file).to_str().unwrap()).unwrap();
+
+        resolve(loader::load(&url).unwrap(), &url)
+    }
+
+    #[test]
+    fn hoist_external_schemas_and_inline_parameters() {
+        let dir = write_files(
+            "hoist",
+            &[
+                (
+                    "api.json",
+                    r##"{
+                        "swagger": "2.0",
+                        "paths": {
+                            "/items": {
+                                "parameters": [{ "$ref": "#/parameters/PageSize" }],
+                                "get": {
+                                    "parameters": [{ "$ref": "#/parameters/PageSize" }],
+                                    "responses": {
+                                        "200": { "schema": { "$ref": "#/definitions/Item" } },
+                                        "400": { "schema": { "$ref": "./common.yaml#/definitions/Error" } },
+                                        "500": { "schema": { "$ref": "common.yaml#/definitions/Error" } }
+                                    }
+                                }
+                            }
+                        },
+                        "parameters": {
+                            "PageSize": { "name": "page_size", "in": "query", "type": "integer" }
+                        },
+                        "definitions": {
+                            "Item": { "type": "object" },
+                            "Error": { "type": "string" }
+                        }
+                    }"##,
+                ),
+                (
+                    "common.yaml",
+                    "definitions:\n  Error:\n    type: object\n    properties:\n      detail:\n        $ref: '#/definitions/Detail'\n  Detail:\n    type: object\n    properties:\n      parent:\n        $ref: '#/definitions/Error'\n",
+                ),
+            ],
+        );
+
+        let document = resolve_file(&dir, "api.json").unwrap();
+        let op = &document["paths"]["/items"]["get"];
+
+        assert_eq!(op["parameters"][0]["name"], "page_size");
+        assert_eq!(
+            document["paths"]["/items"]["parameters"][0]["name"],
+            "page_size"
+        );
+        assert_eq!(
+            op["responses"]["200"]["schema"]["$ref"],
+            "#/definitions/Item"
+        );
+        assert_eq!(
+            op["responses"]["400"]["schema"]["$ref"],
+            "#/definitions/Error_2"
+        );
+        assert_eq!(
+            op["responses"]["500"]["schema"]["$ref"],
+            "#/definitions/Error_2"
+        );
+
+        let definitions = &document["definitions"];
+        assert_eq!(
+            definitions["Error_2"]["properties"]["detail"]["$ref"],
+            "#/definitions/Detail"
+        );
+        assert_eq!(
+            definitions["Detail"]["properties"]["parent"]["$ref"],
+            "#/definitions/Error_2"
+        );
+    }
+
+    #[test]
+    fn reject_circular_inline_refs() {
+        let dir = write_files(
+            "cycle",
+            &[(
+                "api.json",
+                r##"{
+                    "openapi": "3.0.0",
+                    "paths": {},
+                    "components": {
+                        "parameters": {
+                            "A": { "$ref": "#/components/parameters/B" },
+                            "B": { "$ref": "#/components/parameters/A" }
+                        }
+                    }
+                }"##,
+            )],
+        );
+
+        let err = resolve_file(&dir, "api.json").unwrap_err();
+
+        assert!(err.to_string().contains("Circular $ref"));
+    }
+}
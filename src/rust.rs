@@ -0,0 +1,1342 @@
+use anyhow::Result;
+use log::warn;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::generator::{ordered, ordered_operations, ordered_properties, Generator, OutputFile};
+use crate::ir;
+use crate::naming::NamingStyle;
+use crate::options::Options;
+use crate::sanitizer;
+
+/// Prelude types the generated models refer to.
+const PRELUDE_TYPES: &[&str] = &["Box", "Option", "String", "Vec", "Self"];
+/// Associated functions of the generated clients.
+const CLIENT_METHODS: &[&str] = &["new", "with_client"];
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+/// Keywords that can't be used as raw identifiers.
+const NON_RAW_KEYWORDS: &[&str] = &["crate", "self", "Self", "super"];
+
+/// A client crate: serde models and a `reqwest` based client with one method
+/// per operation, plus a blocking client behind the `blocking` feature.
+/// `path` is the crate directory.
+pub struct Rust;
+
+impl Generator for Rust {
+    fn generate(&self, api: ir::Api, options: &Options, path: &Path) -> Result<Vec<OutputFile>> {
+        let mut models = Models::new(&api, options);
+        models.declare_types();
+
+        let mut names = sanitizer::Identifiers::new(CLIENT_METHODS);
+        let methods: Vec<Method> = ordered_operations(&api.operations, options)
+            .into_iter()
+            .map(|op| models.method(op, &mut names))
+            .collect();
+        let multipart = methods.iter().any(|method| method.multipart.is_some());
+
+        Ok(vec![
+            OutputFile {
+                path: path.join("Cargo.toml"),
+                contents: generate_manifest(&crate_name(path), multipart),
+            },
+            OutputFile {
+                path: path.join("src").join("lib.rs"),
+                contents: generate_lib(&api.base_url, &models.items, &methods),
+            },
+        ])
+    }
+}
+
+/// Client method of an operation, rendered once for the async client and once
+/// for the blocking one.
+struct Method {
+    name: String,
+    description: String,
+    http_method: &'static str,
+    /// `format!` arguments building the URL.
+    url: String,
+    /// Argument name and type, in declaration order.
+    arguments: Vec<(String, String)>,
+    /// Statements adding query, header and cookie parameters to `request`.
+    params: Vec<String>,
+    encoding: Option<ir::BodyEncoding>,
+    /// Statements adding each field of a multipart body to `form`.
+    multipart: Option<Vec<String>>,
+    response: String,
+    response_encoding: ir::ResponseEncoding,
+}
+
+/// Named types of the API and the items declared for them, inline objects,
+/// enums and unions are hoisted into items of their own.
+struct Models<'a> {
+    api: &'a ir::Api,
+    options: &'a Options,
+    /// Spec name -> Rust name of every named type.
+    names: HashMap<String, String>,
+    /// Named types declared as items but nullable, referenced as `Option<T>`.
+    nullable: HashSet<String>,
+    recursive: HashSet<String>,
+    /// Rust name -> discriminator properties of the tagged enums the type is
+    /// a variant of.
+    tags: HashMap<String, HashSet<String>>,
+    types: sanitizer::Identifiers,
+    items: Vec<String>,
+    /// Path to the models from where the current type is used.
+    prefix: &'static str,
+}
+
+impl<'a> Models<'a> {
+    fn new(api: &'a ir::Api, options: &'a Options) -> Models<'a> {
+        let mut types = sanitizer::Identifiers::new(PRELUDE_TYPES);
+        let names: HashMap<String, String> = ordered(&api.types, options)
+            .into_iter()
+            .map(|(key, _)| {
+                let name = types.claim(&type_ident(key), &format!("schema {key}"));
+                (key.clone(), name)
+            })
+            .collect();
+        let tags = api
+            .variant_tags()
+            .into_iter()
+            .filter_map(|(key, tags)| Some((names.get(&key)?.clone(), tags.into_keys().collect())))
+            .collect();
+        let nullable = api
+            .types
+            .iter()
+            .filter(|(_, ty)| matches!(ty, ir::Type::Nullable(inner) if is_item(inner)))
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        Models {
+            api,
+            options,
+            names,
+            nullable,
+            recursive: api.recursive_types(),
+            tags,
+            types,
+            items: Vec::new(),
+            prefix: "",
+        }
+    }
+
+    fn declare_types(&mut self) {
+        let api = self.api;
+
+        ordered(&api.types, self.options)
+            .into_iter()
+            .for_each(|(key, _)| {
+                let name = self.names[key].clone();
+                let ty = match &api.types[key] {
+                    ir::Type::Nullable(inner) if self.nullable.contains(key) => inner.as_ref(),
+                    ty => ty,
+                };
+
+                if is_item(ty) {
+                    return self.item(&name, ty);
+                }
+
+                let index = self.items.len();
+                let target = self.rust_type(ty, &name, false);
+
+                // Recursive aliases can't be expanded, they become newtypes.
+                let code = match self.recursive.contains(key) {
+                    true => format!("{DERIVE}pub struct {name}(pub {target});\n"),
+                    false => format!("pub type {name} = {target};\n"),
+                };
+                self.items.insert(index, code);
+            });
+    }
+
+    /// Declares `name` for an object, string enum, union or intersection.
+    /// Nested items are declared after it.
+    fn item(&mut self, name: &str, ty: &ir::Type) {
+        let prefix = std::mem::take(&mut self.prefix);
+        let index = self.items.len();
+
+        let code = match ty {
+            ir::Type::Object(object) => {
+                let properties = ordered_properties(&object.properties, self.options);
+                self.render_struct(name, &properties, object.additional.as_deref(), Vec::new())
+            }
+            ir::Type::Enum(values) => render_enum(name, values),
+            ir::Type::Union(members) => self.render_union(name, ty, members),
+            ir::Type::Intersection(members) => self.render_intersection(name, members),
+            _ => unreachable!("{name} is not declared as an item"),
+        };
+
+        self.items.insert(index, code);
+        self.prefix = prefix;
+    }
+
+    /// Declares an inline type under a name derived from where it is used.
+    fn hoist(&mut self, hint: &str, ty: &ir::Type) -> String {
+        let name = self
+            .types
+            .claim(&type_ident(hint), &format!("inline schema {hint}"));
+        self.item(&name, ty);
+
+        format!("{}{name}", self.prefix)
+    }
+
+    /// Rust type of `ty`. `boxed` is set where recursive types need a `Box`,
+    /// i.e. anywhere but in a `Vec` or a map.
+    fn rust_type(&mut self, ty: &ir::Type, hint: &str, boxed: bool) -> String {
+        match ty {
+            ir::Type::Any | ir::Type::Never => "serde_json::Value".to_string(),
+            ir::Type::Null => "()".to_string(),
+            ir::Type::Boolean => "bool".to_string(),
+            ir::Type::Integer => "i64".to_string(),
+            ir::Type::Number => "f64".to_string(),
+            ir::Type::String => "String".to_string(),
+            ir::Type::File => "Vec<u8>".to_string(),
+            ir::Type::Literal(value) => literal_type(value).to_string(),
+            ir::Type::Enum(_) if is_item(ty) => self.hoist(hint, ty),
+            ir::Type::Enum(values) => {
+                let (nulls, values): (Vec<_>, Vec<_>) = values.iter().partition(|v| v.is_null());
+                let types: HashSet<&str> = values.into_iter().map(literal_type).collect();
+
+                let inner = match types.len() {
+                    1 => types.into_iter().next().unwrap(),
+                    _ => "serde_json::Value",
+                };
+
+                match nulls.is_empty() {
+                    true => inner.to_string(),
+                    false => format!("Option<{inner}>"),
+                }
+            }
+            ir::Type::Array(items) => {
+                format!(
+                    "Vec<{}>",
+                    self.rust_type(items, &format!("{hint}Item"), false)
+                )
+            }
+            ir::Type::Tuple { items, rest: None } => {
+                let items: Vec<String> = items
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, item)| self.rust_type(item, &format!("{hint}{}", idx + 1), boxed))
+                    .collect();
+
+                match items.len() {
+                    1 => format!("({},)", items[0]),
+                    _ => format!("({})", items.join(", ")),
+                }
+            }
+            ir::Type::Tuple { rest: Some(_), .. } => "Vec<serde_json::Value>".to_string(),
+            ir::Type::Object(object) if object.properties.is_empty() => {
+                let value = match object.additional.as_deref() {
+                    Some(ir::Type::Never) | None => "serde_json::Value".to_string(),
+                    Some(ty) => self.rust_type(ty, &format!("{hint}Value"), false),
+                };
+
+                format!("std::collections::HashMap<String, {value}>")
+            }
+            ir::Type::Object(_) | ir::Type::Union(_) | ir::Type::Intersection(_) => {
+                self.hoist(hint, ty)
+            }
+            ir::Type::Ref(name) => self.reference(name, boxed),
+            ir::Type::Variant { ty, .. } => self.rust_type(ty, hint, boxed),
+            ir::Type::Nullable(inner) => match self.rust_type(inner, hint, boxed) {
+                inner if inner.starts_with("Option<") => inner,
+                inner => format!("Option<{inner}>"),
+            },
+        }
+    }
+
+    fn reference(&self, name: &str, boxed: bool) -> String {
+        let rust_name = match self.names.get(name) {
+            Some(rust_name) => format!("{}{rust_name}", self.prefix),
+            None => {
+                warn!("Reference to unknown schema {}", name);
+                "serde_json::Value".to_string()
+            }
+        };
+
+        let rust_name = match boxed && self.recursive.contains(name) {
+            true => format!("Box<{rust_name}>"),
+            false => rust_name,
+        };
+
+        match self.nullable.contains(name) {
+            true => format!("Option<{rust_name}>"),
+            false => rust_name,
+        }
+    }
+
+    /// A struct with `properties`, undeclared properties collected in a map
+    /// and `flattened` fields (name and type) whose own fields are inlined.
+    fn render_struct(
+        &mut self,
+        name: &str,
+        properties: &[&ir::Property],
+        additional: Option<&ir::Type>,
+        flattened: Vec<(String, String)>,
+    ) -> String {
+        let mut taken = sanitizer::Identifiers::new(&[]);
+        let idents = field_idents(properties, name, &mut taken);
+        let tags = self.tags.get(name).cloned().unwrap_or_default();
+        let mut attributes = String::new();
+        let mut fields = String::new();
+
+        properties
+            .iter()
+            .zip(idents.iter())
+            .for_each(|(prop, ident)| {
+                let hint = format!("{name}{}", type_ident(&prop.name));
+                let ty = self.rust_type(&prop.ty, &hint, true);
+
+                let mut serde = Vec::new();
+                if ident.trim_start_matches("r#") != prop.name {
+                    serde.push(format!("rename = {:?}", prop.name));
+                }
+
+                // Tagged enums consume the discriminator before deserializing
+                // the variant, so the field is only set outside of them.
+                let ty = match prop.required && !tags.contains(&prop.name) {
+                    true => ty,
+                    false => {
+                        serde.push("default".to_string());
+                        serde.push("skip_serializing_if = \"Option::is_none\"".to_string());
+
+                        match ty.starts_with("Option<") {
+                            true => ty,
+                            false => format!("Option<{ty}>"),
+                        }
+                    }
+                };
+
+                if let Some(description) = &prop.description {
+                    fields.push_str(&doc(description, "    "));
+                }
+                if !serde.is_empty() {
+                    fields.push_str(&format!("    #[serde({})]\n", serde.join(", ")));
+                }
+                fields.push_str(&format!("    pub {ident}: {ty},\n"));
+            });
+
+        flattened.iter().for_each(|(field, ty)| {
+            let field = taken.claim(field, &format!("flattened {ty} of {name}"));
+            fields.push_str(&format!("    #[serde(flatten)]\n    pub {field}: {ty},\n"));
+        });
+
+        match additional {
+            // `deny_unknown_fields` doesn't work along with flattened fields.
+            Some(ir::Type::Never) if flattened.is_empty() => {
+                attributes.push_str("#[serde(deny_unknown_fields)]\n");
+            }
+            Some(ir::Type::Never) | None => (),
+            Some(ty) => {
+                let ty = self.rust_type(ty, &format!("{name}Value"), false);
+                let field = taken.claim(
+                    "additional_properties",
+                    &format!("additional properties of {name}"),
+                );
+
+                fields.push_str(&format!(
+                    "    #[serde(flatten)]\n    pub {field}: std::collections::HashMap<String, {ty}>,\n"
+                ));
+            }
+        }
+
+        format!("{DERIVE}{attributes}pub struct {name} {{\n{fields}}}\n")
+    }
+
+    /// Enum tagged with the discriminator property when every member is a
+    /// variant, otherwise an untagged enum trying each member in order.
+    fn render_union(&mut self, name: &str, ty: &ir::Type, members: &[ir::Type]) -> String {
+        let mut taken = sanitizer::Identifiers::new(&[]).separated_by("");
+
+        let variants: String = members
+            .iter()
+            .enumerate()
+            .map(|(idx, member)| {
+                let (variant, payload) = match member {
+                    ir::Type::Null => ("Null".to_string(), None),
+                    ir::Type::Variant { value, ty, .. } => {
+                        let variant = variant_ident(value);
+                        let hint = format!("{name}{variant}");
+                        (variant, Some(self.rust_type(ty, &hint, true)))
+                    }
+                    ir::Type::Ref(target) => (
+                        self.names
+                            .get(target)
+                            .cloned()
+                            .unwrap_or_else(|| type_ident(target)),
+                        Some(self.reference(target, true)),
+                    ),
+                    other => {
+                        let hint = format!("{name}{}", idx + 1);
+                        (
+                            kind_name(other).to_string(),
+                            Some(self.rust_type(other, &hint, true)),
+                        )
+                    }
+                };
+
+                let variant = taken.claim(&variant, &format!("member {} of {name}", idx + 1));
+                let rename = match member {
+                    ir::Type::Variant { value, .. } if ty.discriminator().is_some() => {
+                        format!("    #[serde(rename = {value:?})]\n")
+                    }
+                    _ => String::new(),
+                };
+                match payload {
+                    Some(payload) => format!("{rename}    {variant}({payload}),\n"),
+                    None => format!("{rename}    {variant},\n"),
+                }
+            })
+            .collect();
+
+        let tag = match ty.discriminator() {
+            Some(property) => format!("tag = {property:?}"),
+            None => "untagged".to_string(),
+        };
+
+        format!("{DERIVE}#[serde({tag})]\npub enum {name} {{\n{variants}}}\n")
+    }
+
+    /// Struct with the properties of inline object members and the other
+    /// members flattened into it.
+    fn render_intersection(&mut self, name: &str, members: &[ir::Type]) -> String {
+        let mut properties = Vec::new();
+        let mut additional = None;
+        let mut flattened = Vec::new();
+
+        members.iter().enumerate().for_each(|(idx, member)| {
+            let member = match member {
+                ir::Type::Variant { ty, .. } => ty.as_ref(),
+                member => member,
+            };
+
+            match member {
+                ir::Type::Object(object) => {
+                    properties.extend(object.properties.iter());
+                    additional = additional.or(object.additional.as_deref());
+                }
+                ir::Type::Ref(target) => {
+                    let field = self
+                        .names
+                        .get(target)
+                        .map(|rust_name| field_ident(rust_name))
+                        .unwrap_or_else(|| format!("part_{}", idx + 1));
+                    flattened.push((field, self.reference(target, true)));
+                }
+                other => {
+                    let hint = format!("{name}Part{}", idx + 1);
+                    flattened.push((
+                        format!("part_{}", idx + 1),
+                        self.rust_type(other, &hint, true),
+                    ));
+                }
+            }
+        });
+
+        let properties = ordered_properties(properties, self.options);
+
+        self.render_struct(name, &properties, additional, flattened)
+    }
+
+    fn method(&mut self, op: &ir::Operation, names: &mut sanitizer::Identifiers) -> Method {
+        self.prefix = "models::";
+
+        let base = match (self.options.use_operation_id, &op.operation_id) {
+            (true, Some(operation_id)) => operation_id.clone(),
+            _ => sanitizer::create_fn_name_from_path(op.method.as_str(), &op.path),
+        };
+        let name = names.claim(
+            &field_ident(&base),
+            &format!("{} {}", op.method.as_str(), op.path),
+        );
+        let hint = type_ident(&name);
+
+        // locals of the generated method
+        let mut taken = sanitizer::Identifiers::new(&[
+            "body", "request", "url", "response", "status", "text", "form", "cookies",
+        ]);
+        let mut arguments = Vec::new();
+        let mut params = Vec::new();
+        let mut cookies = Vec::new();
+        let mut path_args: HashMap<&str, String> = HashMap::new();
+
+        op.parameters.iter().for_each(|param| {
+            let ident = taken.claim(
+                &field_ident(&param.name),
+                &format!("parameter {} of {name}", param.name),
+            );
+            let ty = self.rust_type(
+                &param.ty,
+                &format!("{hint}{}", type_ident(&param.name)),
+                false,
+            );
+            let ty = match (param.required && !param.nullable) || ty.starts_with("Option<") {
+                true => ty,
+                false => format!("Option<{ty}>"),
+            };
+            let format = param.serialization.map_or("csv", |s| s.as_str());
+            let serialized = format!("serialize_param({:?}, &{ident}, {format:?})", param.name);
+
+            match param.location {
+                ir::ParameterLocation::Path => {
+                    path_args.insert(&param.name, format!("path_param(&{ident}, {format:?})"));
+                }
+                ir::ParameterLocation::Query => {
+                    params.push(format!("request = request.query(&{serialized});"));
+                }
+                ir::ParameterLocation::Header => params.push(format!(
+                    "for (name, value) in {serialized} {{\n            request = request.header(name, value);\n        }}"
+                )),
+                ir::ParameterLocation::Cookie => cookies.push(serialized),
+            }
+
+            arguments.push((ident, ty));
+        });
+
+        if !cookies.is_empty() {
+            params.push(format!(
+                "let cookies: Vec<String> = [{}]\n            .concat()\n            .into_iter()\n            .map(|(name, value)| format!(\"{{name}}={{}}\", encode_path(&value)))\n            .collect();\n        if !cookies.is_empty() {{\n            request = request.header(\"Cookie\", cookies.join(\"; \"));\n        }}",
+                cookies.join(", ")
+            ));
+        }
+
+        let mut url = String::new();
+        let mut url_args = Vec::new();
+        op.path_parts().into_iter().for_each(|part| match part {
+            ir::PathPart::Placeholder(name) if path_args.contains_key(name) => {
+                url.push_str("{}");
+                url_args.push(path_args[name].clone());
+            }
+            ir::PathPart::Placeholder(name) => {
+                warn!(
+                    "No path parameter {} declared for {} {}, keeping the placeholder",
+                    name,
+                    op.method.as_str(),
+                    op.path
+                );
+                url.push_str(&format_literal(&format!("{{{name}}}")));
+            }
+            ir::PathPart::Literal(text) => url.push_str(&format_literal(text)),
+        });
+        let url = format!(
+            "\"{{}}{}\", self.base_url{}",
+            url,
+            url_args
+                .iter()
+                .map(|arg| format!(", {arg}"))
+                .collect::<String>()
+        );
+
+        let (encoding, multipart) = match &op.body {
+            Some(body) => {
+                let ty = self.rust_type(&body.ty, &format!("{hint}Body"), false);
+                arguments.push(("body".to_string(), format!("&{ty}")));
+
+                let multipart = match body.encoding {
+                    ir::BodyEncoding::Multipart => Some(self.multipart_fields(&body.ty)),
+                    _ => None,
+                };
+
+                (Some(body.encoding), multipart)
+            }
+            None => (None, None),
+        };
+
+        let mut responses: Vec<&ir::Type> = Vec::new();
+        op.responses_in(&["2"])
+            .filter_map(|res| res.ty.as_ref())
+            .for_each(|ty| {
+                if !responses.contains(&ty) {
+                    responses.push(ty);
+                }
+            });
+        let response_encoding = op.response_encoding();
+
+        let response = match (response_encoding, responses.as_slice()) {
+            (_, []) => "()".to_string(),
+            (ir::ResponseEncoding::Text, _) => "String".to_string(),
+            (ir::ResponseEncoding::Binary, _) => "Vec<u8>".to_string(),
+            (ir::ResponseEncoding::Json, [ty]) => {
+                self.rust_type(ty, &format!("{hint}Response"), false)
+            }
+            (ir::ResponseEncoding::Json, _) => "serde_json::Value".to_string(),
+        };
+
+        Method {
+            name,
+            description: op.description.clone(),
+            http_method: op.method.as_str(),
+            url,
+            arguments,
+            params,
+            encoding,
+            multipart,
+            response,
+            response_encoding,
+        }
+    }
+
+    /// Statements adding each field of a multipart body to `form`, files as
+    /// bytes, objects as JSON and anything else as text.
+    fn multipart_fields(&self, ty: &ir::Type) -> Vec<String> {
+        let object = match ty {
+            ir::Type::Object(object) => Some(object),
+            ir::Type::Ref(name) => match self.api.types.get(name) {
+                Some(ir::Type::Object(object)) => Some(object),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        let Some(object) = object else {
+            warn!("Multipart body without properties, sending it as a single JSON field");
+            return vec![
+                "form = form.text(\"body\", serde_json::to_string(body).unwrap_or_default());"
+                    .to_string(),
+            ];
+        };
+
+        let properties = ordered_properties(&object.properties, self.options);
+
+        properties
+            .iter()
+            .zip(field_idents(
+                &properties,
+                "the multipart body",
+                &mut sanitizer::Identifiers::new(&[]),
+            ))
+            .map(|(prop, ident)| match (&prop.ty, prop.required) {
+                (ir::Type::File, true) => format!(
+                    "form = form.part({:?}, Part::bytes(body.{ident}.clone()));",
+                    prop.name
+                ),
+                (ir::Type::File, false) => format!(
+                    "if let Some(value) = &body.{ident} {{\n            form = form.part({:?}, Part::bytes(value.clone()));\n        }}",
+                    prop.name
+                ),
+                _ => format!(
+                    "for (name, value) in form_fields({:?}, &body.{ident}) {{\n            form = form.text(name, value);\n        }}",
+                    prop.name
+                ),
+            })
+            .collect()
+    }
+}
+
+const DERIVE: &str = "#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]\n";
+
+/// Object, string enum, union or intersection, declared as an item of its
+/// own.
+fn is_item(ty: &ir::Type) -> bool {
+    match ty {
+        ir::Type::Object(object) => !object.properties.is_empty(),
+        ir::Type::Enum(values) => !values.is_empty() && values.iter().all(|v| v.is_string()),
+        ir::Type::Union(_) | ir::Type::Intersection(_) => true,
+        _ => false,
+    }
+}
+
+fn render_enum(name: &str, values: &[serde_json::Value]) -> String {
+    let mut taken = sanitizer::Identifiers::new(&[]).separated_by("");
+
+    let variants: String = values
+        .iter()
+        .filter_map(serde_json::Value::as_str)
+        .map(|value| {
+            let variant = taken.claim(&variant_ident(value), &format!("value {value} of {name}"));
+
+            match variant == value {
+                true => format!("    {variant},\n"),
+                false => format!("    #[serde(rename = {value:?})]\n    {variant},\n"),
+            }
+        })
+        .collect();
+
+    format!(
+        "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]\npub enum {name} {{\n{variants}}}\n"
+    )
+}
+
+fn literal_type(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::String(_) => "String",
+        serde_json::Value::Number(number) if number.is_i64() => "i64",
+        serde_json::Value::Number(_) => "f64",
+        serde_json::Value::Bool(_) => "bool",
+        serde_json::Value::Null => "()",
+        _ => "serde_json::Value",
+    }
+}
+
+/// Variant name of an untagged union member.
+fn kind_name(ty: &ir::Type) -> &'static str {
+    match ty {
+        ir::Type::Boolean => "Boolean",
+        ir::Type::Integer => "Integer",
+        ir::Type::Number => "Number",
+        ir::Type::String | ir::Type::Literal(_) | ir::Type::Enum(_) => "String",
+        ir::Type::File => "File",
+        ir::Type::Array(_) | ir::Type::Tuple { .. } => "Array",
+        ir::Type::Object(_) => "Object",
+        _ => "Value",
+    }
+}
+
+fn type_ident(name: &str) -> String {
+    match NamingStyle::Pascal.apply(name).as_str() {
+        "" => "Type".to_string(),
+        "Self" => "Self_".to_string(),
+        ident => ident.to_string(),
+    }
+}
+
+fn variant_ident(value: &str) -> String {
+    match NamingStyle::Pascal.apply(value).as_str() {
+        "" => "Empty".to_string(),
+        "Self" => "Self_".to_string(),
+        ident => ident.to_string(),
+    }
+}
+
+/// Snake case identifier, keywords are escaped as raw identifiers.
+fn field_ident(name: &str) -> String {
+    let ident = match NamingStyle::Snake.apply(name) {
+        ident if ident.is_empty() => "value".to_string(),
+        ident => ident,
+    };
+
+    match KEYWORDS.contains(&ident.as_str()) {
+        true if NON_RAW_KEYWORDS.contains(&ident.as_str()) => format!("{ident}_"),
+        true => format!("r#{ident}"),
+        false => ident,
+    }
+}
+
+/// Field names of the `properties` of `owner`, suffixed where two of them
+/// collide.
+fn field_idents(
+    properties: &[&ir::Property],
+    owner: &str,
+    taken: &mut sanitizer::Identifiers,
+) -> Vec<String> {
+    properties
+        .iter()
+        .map(|prop| {
+            taken.claim(
+                &field_ident(&prop.name),
+                &format!("property {} of {owner}", prop.name),
+            )
+        })
+        .collect()
+}
+
+/// `text` escaped for the string literal of a `format!` call.
+fn format_literal(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('{', "{{")
+        .replace('}', "}}")
+}
+
+fn doc(text: &str, indent: &str) -> String {
+    text.trim()
+        .lines()
+        .map(|line| format!("{indent}/// {line}").trim_end().to_string() + "\n")
+        .collect()
+}
+
+fn crate_name(path: &Path) -> String {
+    let name = path
+        .file_name()
+        .map(|name| NamingStyle::Snake.apply(&name.to_string_lossy()))
+        .unwrap_or_default()
+        .trim_start_matches('_')
+        .replace('_', "-");
+
+    match name.is_empty() {
+        true => "api-client".to_string(),
+        false => name,
+    }
+}
+
+fn generate_manifest(name: &str, multipart: bool) -> String {
+    let features = match multipart {
+        true => "\"json\", \"multipart\"",
+        false => "\"json\"",
+    };
+
+    format!(
+        r#"[package]
+name = "{name}"
+version = "0.1.0"
+edition = "2021"
+
+[features]
+blocking = ["reqwest/blocking"]
+
+[dependencies]
+reqwest = {{ version = "0.11", features = [{features}] }}
+serde = {{ version = "1", features = ["derive"] }}
+serde_json = "1"
+"#
+    )
+}
+
+fn generate_lib(base_url: &str, items: &[String], methods: &[Method]) -> String {
+    let models = items
+        .iter()
+        .map(|item| {
+            item.lines()
+                .map(|line| match line.is_empty() {
+                    true => "\n".to_string(),
+                    false => format!("    {line}\n"),
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let render = |blocking| {
+        methods
+            .iter()
+            .map(|method| generate_method(method, blocking))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let (methods, blocking_methods) = (render(false), render(true));
+
+    format!(
+        r#"//! API client generated by openapi-gen, do not edit.
+
+// Colliding names are disambiguated with `_2`, `_3`, ... suffixes.
+#![allow(dead_code, non_camel_case_types)]
+
+pub const BASE_URL: &str = {base_url:?};
+
+pub mod models {{
+{models}}}
+
+#[derive(Debug)]
+pub enum Error {{
+    Request(reqwest::Error),
+    Decode(serde_json::Error),
+    /// The API answered with an error status.
+    Status {{
+        status: reqwest::StatusCode,
+        body: String,
+    }},
+}}
+
+impl std::fmt::Display for Error {{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {{
+        match self {{
+            Error::Request(err) => write!(f, "request failed: {{err}}"),
+            Error::Decode(err) => write!(f, "unable to decode the response: {{err}}"),
+            Error::Status {{ status, body }} => write!(f, "the API answered {{status}}: {{body}}"),
+        }}
+    }}
+}}
+
+impl std::error::Error for Error {{}}
+
+impl From<reqwest::Error> for Error {{
+    fn from(err: reqwest::Error) -> Self {{
+        Error::Request(err)
+    }}
+}}
+
+fn scalar(value: &serde_json::Value) -> String {{
+    match value {{
+        serde_json::Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }}
+}}
+
+/// Name and value pairs of a parameter, arrays and objects serialized in
+/// `format` (`csv`, `ssv`, `tsv`, `pipes`, `multi`, `form` or `deepObject`).
+fn serialize_param<T: serde::Serialize>(key: &str, value: &T, format: &str) -> Vec<(String, String)> {{
+    let delimiter = match format {{
+        "ssv" => " ",
+        "tsv" => "\t",
+        "pipes" => "|",
+        _ => ",",
+    }};
+
+    match serde_json::to_value(value).unwrap_or_default() {{
+        serde_json::Value::Null => Vec::new(),
+        serde_json::Value::Array(items) => match format {{
+            "multi" | "form" => items.iter().map(|item| (key.to_string(), scalar(item))).collect(),
+            _ => vec![(key.to_string(), items.iter().map(scalar).collect::<Vec<_>>().join(delimiter))],
+        }},
+        serde_json::Value::Object(entries) => {{
+            let entries = entries.iter().filter(|(_, item)| !item.is_null());
+
+            match format {{
+                "deepObject" => entries.map(|(prop, item)| (format!("{{key}}[{{prop}}]"), scalar(item))).collect(),
+                "form" | "multi" => entries.map(|(prop, item)| (prop.clone(), scalar(item))).collect(),
+                _ => vec![(
+                    key.to_string(),
+                    entries
+                        .flat_map(|(prop, item)| [prop.clone(), scalar(item)])
+                        .collect::<Vec<_>>()
+                        .join(delimiter),
+                )],
+            }}
+        }}
+        value => vec![(key.to_string(), scalar(&value))],
+    }}
+}}
+
+/// Name and value pairs of a form field, objects are sent as JSON under their
+/// own name.
+fn form_fields<T: serde::Serialize>(key: &str, value: &T) -> Vec<(String, String)> {{
+    match serde_json::to_value(value).unwrap_or_default() {{
+        value @ serde_json::Value::Object(_) => vec![(key.to_string(), value.to_string())],
+        value => serialize_param(key, &value, "multi"),
+    }}
+}}
+
+fn form_body<T: serde::Serialize>(body: &T) -> Vec<(String, String)> {{
+    match serde_json::to_value(body).unwrap_or_default() {{
+        serde_json::Value::Object(fields) => fields.iter().flat_map(|(key, value)| form_fields(key, value)).collect(),
+        _ => Vec::new(),
+    }}
+}}
+
+fn encode_path(value: &str) -> String {{
+    value
+        .bytes()
+        .map(|byte| match byte {{
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+            _ => format!("%{{byte:02X}}"),
+        }})
+        .collect()
+}}
+
+fn path_param<T: serde::Serialize>(value: &T, format: &str) -> String {{
+    let value = serialize_param("", value, format).into_iter().next().unwrap_or_default().1;
+
+    encode_path(&value)
+}}
+
+fn decode<T: serde::de::DeserializeOwned>(text: &str) -> Result<T, Error> {{
+    // Empty bodies, e.g. `204 No Content`, decode as `null`.
+    serde_json::from_str(if text.is_empty() {{ "null" }} else {{ text }}).map_err(Error::Decode)
+}}
+
+#[derive(Debug, Clone)]
+pub struct Client {{
+    client: reqwest::Client,
+    base_url: String,
+}}
+
+impl Default for Client {{
+    fn default() -> Self {{
+        Self::new()
+    }}
+}}
+
+impl Client {{
+    pub fn new() -> Self {{
+        Self::with_client(reqwest::Client::new(), BASE_URL)
+    }}
+
+    pub fn with_client(client: reqwest::Client, base_url: impl Into<String>) -> Self {{
+        Client {{
+            client,
+            base_url: base_url.into(),
+        }}
+    }}
+
+{methods}}}
+
+#[cfg(feature = "blocking")]
+pub mod blocking {{
+    use super::*;
+
+    #[derive(Debug, Clone)]
+    pub struct Client {{
+        client: reqwest::blocking::Client,
+        base_url: String,
+    }}
+
+    impl Default for Client {{
+        fn default() -> Self {{
+            Self::new()
+        }}
+    }}
+
+    impl Client {{
+        pub fn new() -> Self {{
+            Self::with_client(reqwest::blocking::Client::new(), BASE_URL)
+        }}
+
+        pub fn with_client(client: reqwest::blocking::Client, base_url: impl Into<String>) -> Self {{
+            Client {{
+                client,
+                base_url: base_url.into(),
+            }}
+        }}
+
+{blocking_methods}    }}
+}}
+"#
+    )
+}
+
+/// Method of the async client, or of the blocking one indented one level
+/// deeper.
+fn generate_method(method: &Method, blocking: bool) -> String {
+    let (asyncness, awaited, multipart) = match blocking {
+        true => ("", "", "reqwest::blocking::multipart"),
+        false => ("async ", ".await", "reqwest::multipart"),
+    };
+
+    let mutable = match method.params.is_empty() && method.encoding.is_none() {
+        true => "",
+        false => "mut ",
+    };
+    let mut body = vec![
+        format!("let url = format!({});", method.url),
+        format!(
+            "let {mutable}request = self.client.request(reqwest::Method::{}, url);",
+            method.http_method
+        ),
+    ];
+    body.extend(method.params.iter().cloned());
+
+    match (&method.encoding, &method.multipart) {
+        (_, Some(fields)) => {
+            // `Part` is only used by file fields.
+            match fields.iter().any(|field| field.contains("Part::")) {
+                true => body.push(format!("use {multipart}::{{Form, Part}};")),
+                false => body.push(format!("use {multipart}::Form;")),
+            }
+            body.push("let mut form = Form::new();".to_string());
+            body.extend(fields.iter().cloned());
+            body.push("request = request.multipart(form);".to_string());
+        }
+        (Some(ir::BodyEncoding::UrlEncoded), None) => {
+            body.push("request = request.form(&form_body(body));".to_string())
+        }
+        (Some(_), None) => body.push("request = request.json(body);".to_string()),
+        (None, None) => (),
+    }
+
+    body.push(format!("let response = request.send(){awaited}?;"));
+    body.push("let status = response.status();".to_string());
+    match (method.response.as_str(), method.response_encoding) {
+        // the body is only read as text to report an error status
+        (response, ir::ResponseEncoding::Binary) if response != "()" => body.push(format!(
+            "if !status.is_success() {{\n            return Err(Error::Status {{ status, body: response.text(){awaited}? }});\n        }}"
+        )),
+        _ => {
+            body.push(format!("let text = response.text(){awaited}?;"));
+            body.push(
+                "if !status.is_success() {\n            return Err(Error::Status { status, body: text });\n        }"
+                    .to_string(),
+            );
+        }
+    }
+    body.push(match (method.response.as_str(), method.response_encoding) {
+        ("()", _) => "Ok(())".to_string(),
+        (_, ir::ResponseEncoding::Json) => "decode(&text)".to_string(),
+        (_, ir::ResponseEncoding::Text) => "Ok(text)".to_string(),
+        (_, ir::ResponseEncoding::Binary) => format!("Ok(response.bytes(){awaited}?.to_vec())"),
+    });
+
+    let arguments: String = method
+        .arguments
+        .iter()
+        .map(|(name, ty)| format!(", {name}: {ty}"))
+        .collect();
+
+    let code = format!(
+        "{}    pub {asyncness}fn {}(&self{arguments}) -> Result<{}, Error> {{\n{}    }}\n",
+        doc(&method.description, "    "),
+        method.name,
+        method.response,
+        body.iter()
+            .map(|statement| format!("        {statement}\n"))
+            .collect::<String>()
+    );
+
+    match blocking {
+        true => code
+            .lines()
+            .map(|line| match line.is_empty() {
+                true => "\n".to_string(),
+                false => format!("    {line}\n"),
+            })
+            .collect(),
+        false => code,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn property(name: &str, required: bool, ty: ir::Type) -> ir::Property {
+        ir::Property {
+            name: name.to_string(),
+            description: None,
+            required,
+            ty,
+        }
+    }
+
+    #[test]
+    fn declare_serde_models() {
+        let api = ir::Api {
+            types: indexmap::IndexMap::from([
+                (
+                    "pet".to_string(),
+                    ir::Type::Object(ir::ObjectType {
+                        properties: vec![
+                            property("type", true, ir::Type::Enum(vec!["cat".into()])),
+                            property("parent", false, ir::Type::Ref("pet".to_string())),
+                        ],
+                        additional: None,
+                    }),
+                ),
+                (
+                    "Id".to_string(),
+                    ir::Type::Union(vec![ir::Type::String, ir::Type::Integer]),
+                ),
+            ]),
+            ..Default::default()
+        };
+        let options = Options::default();
+
+        let mut models = Models::new(&api, &options);
+        models.declare_types();
+
+        assert_eq!(
+            models.items,
+            vec![
+                format!("{DERIVE}#[serde(untagged)]\npub enum Id {{\n    String(String),\n    Integer(i64),\n}}\n"),
+                format!("{DERIVE}pub struct Pet {{\n    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n    pub parent: Option<Box<Pet>>,\n    pub r#type: PetType,\n}}\n"),
+                "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]\npub enum PetType {\n    #[serde(rename = \"cat\")]\n    Cat,\n}\n".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn import_part_for_file_fields_only() {
+        let method = |fields: Vec<&str>| Method {
+            name: "upload".to_string(),
+            description: String::new(),
+            http_method: "POST",
+            url: "\"{}/upload\", self.base_url".to_string(),
+            arguments: vec![],
+            params: vec![],
+            encoding: Some(ir::BodyEncoding::Multipart),
+            multipart: Some(fields.into_iter().map(str::to_string).collect()),
+            response: "()".to_string(),
+            response_encoding: ir::ResponseEncoding::Json,
+        };
+
+        let text = generate_method(&method(vec!["form = form.text(\"a\", \"b\");"]), false);
+        assert!(text.contains("use reqwest::multipart::Form;"));
+        assert!(!text.contains("Part"));
+
+        let file = generate_method(
+            &method(vec![
+                "form = form.part(\"a\", Part::bytes(body.a.clone()));",
+            ]),
+            true,
+        );
+        assert!(file.contains("use reqwest::blocking::multipart::{Form, Part};"));
+    }
+
+    #[test]
+    fn read_responses_by_media_type() {
+        let method = |response: &str, response_encoding| Method {
+            name: "exec".to_string(),
+            description: String::new(),
+            http_method: "POST",
+            url: "\"{}/exec\", self.base_url".to_string(),
+            arguments: vec![],
+            params: vec![],
+            encoding: None,
+            multipart: None,
+            response: response.to_string(),
+            response_encoding,
+        };
+
+        let json = generate_method(&method("models::Pet", ir::ResponseEncoding::Json), false);
+        assert!(json.contains("decode(&text)"));
+
+        let text = generate_method(&method("String", ir::ResponseEncoding::Text), false);
+        assert!(text.contains("let text = response.text().await?;"));
+        assert!(text.contains("Ok(text)"));
+
+        let bytes = generate_method(&method("Vec<u8>", ir::ResponseEncoding::Binary), true);
+        assert!(bytes.contains("body: response.text()? });"));
+        assert!(bytes.contains("Ok(response.bytes()?.to_vec())"));
+        assert!(!bytes.contains("decode("));
+    }
+
+    #[test]
+    fn declare_tagged_enums() {
+        let variant = |value: &str, target: &str| ir::Type::Variant {
+            property: "kind".to_string(),
+            value: value.to_string(),
+            ty: Box::new(ir::Type::Ref(target.to_string())),
+        };
+        let api = ir::Api {
+            types: indexmap::IndexMap::from([
+                (
+                    "Pet".to_string(),
+                    ir::Type::Union(vec![variant("cat", "Cat"), variant("dog", "Dog")]),
+                ),
+                (
+                    "Cat".to_string(),
+                    ir::Type::Object(ir::ObjectType {
+                        properties: vec![property("kind", true, ir::Type::String)],
+                        additional: None,
+                    }),
+                ),
+                (
+                    "Dog".to_string(),
+                    ir::Type::Object(ir::ObjectType::default()),
+                ),
+            ]),
+            ..Default::default()
+        };
+        let options = Options {
+            preserve_spec_order: true,
+            ..Default::default()
+        };
+
+        let mut models = Models::new(&api, &options);
+        models.declare_types();
+
+        assert_eq!(
+            models.items[..2],
+            [
+                format!("{DERIVE}#[serde(tag = \"kind\")]\npub enum Pet {{\n    #[serde(rename = \"cat\")]\n    Cat(Cat),\n    #[serde(rename = \"dog\")]\n    Dog(Dog),\n}}\n"),
+                format!("{DERIVE}pub struct Cat {{\n    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n    pub kind: Option<String>,\n}}\n"),
+            ]
+        );
+    }
+
+    #[test]
+    fn keep_parameters_apart_from_locals() {
+        let api = ir::Api::default();
+        let options = Options::default();
+        let param = |name: &str| ir::Parameter {
+            name: name.to_string(),
+            location: ir::ParameterLocation::Query,
+            required: true,
+            nullable: false,
+            ty: ir::Type::String,
+            serialization: None,
+        };
+        let op = ir::Operation {
+            method: ir::Method::Get,
+            path: "/links".to_string(),
+            operation_id: None,
+            description: String::new(),
+            parameters: vec![param("url"), param("response")],
+            body: None,
+            responses: vec![],
+        };
+
+        let mut models = Models::new(&api, &options);
+        let method = models.method(&op, &mut sanitizer::Identifiers::new(&[]));
+        let code = generate_method(&method, false);
+
+        assert!(code.contains("(&self, url_2: String, response_2: String)"));
+        assert!(code.contains("serialize_param(\"url\", &url_2, \"csv\")"));
+        assert!(code.contains("serialize_param(\"response\", &response_2, \"csv\")"));
+    }
+
+    #[test]
+    fn send_multipart_objects_as_json() {
+        let api = ir::Api::default();
+        let options = Options::default();
+        let body = ir::Type::Object(ir::ObjectType {
+            properties: vec![
+                property("file", true, ir::Type::File),
+                property(
+                    "meta",
+                    true,
+                    ir::Type::Object(ir::ObjectType {
+                        properties: Vec::new(),
+                        additional: Some(Box::new(ir::Type::Integer)),
+                    }),
+                ),
+            ],
+            additional: None,
+        });
+
+        let models = Models::new(&api, &options);
+
+        assert_eq!(
+            models.multipart_fields(&body),
+            vec![
+                "form = form.part(\"file\", Part::bytes(body.file.clone()));",
+                "for (name, value) in form_fields(\"meta\", &body.meta) {\n            form = form.text(name, value);\n        }",
+            ]
+        );
+    }
+
+    #[test]
+    fn keep_undeclared_path_placeholders() {
+        let api = ir::Api::default();
+        let options = Options::default();
+        let op = ir::Operation {
+            method: ir::Method::Get,
+            path: "/pets/{id}/toys/{toy}".to_string(),
+            operation_id: None,
+            description: String::new(),
+            parameters: vec![ir::Parameter {
+                name: "toy".to_string(),
+                location: ir::ParameterLocation::Path,
+                required: true,
+                nullable: false,
+                ty: ir::Type::String,
+                serialization: None,
+            }],
+            body: None,
+            responses: vec![],
+        };
+
+        let mut models = Models::new(&api, &options);
+        let method = models.method(&op, &mut sanitizer::Identifiers::new(&[]));
+
+        assert_eq!(
+            method.url,
+            "\"{}/pets/{{id}}/toys/{}\", self.base_url, path_param(&toy, \"csv\")"
+        );
+    }
+
+    #[test]
+    fn send_url_encoded_fields() {
+        let method = Method {
+            name: "login".to_string(),
+            description: String::new(),
+            http_method: "POST",
+            url: "\"{}/login\", self.base_url".to_string(),
+            arguments: vec![("body".to_string(), "&models::Login".to_string())],
+            params: vec![],
+            encoding: Some(ir::BodyEncoding::UrlEncoded),
+            multipart: None,
+            response: "()".to_string(),
+            response_encoding: ir::ResponseEncoding::Json,
+        };
+
+        assert!(
+            generate_method(&method, false).contains("request = request.form(&form_body(body));")
+        );
+    }
+}
@@ -1,12 +1,344 @@
-pub fn create_input_type_name_from_path(path: &String, prefix: Option<&String>) -> String {
-    let clear = path
-        .replace("/", "_")
-        .replace("{", "")
-        .replace("}", "")
-        .replace("-", "_");
+use log::warn;
+use std::collections::HashMap;
+
+use crate::options::Options;
+
+/// Words that can't name a type or function: JavaScript reserved words and
+/// TypeScript's predefined type names.
+const RESERVED_WORDS: &[&str] = &[
+    "any",
+    "arguments",
+    "await",
+    "bigint",
+    "boolean",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "eval",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "never",
+    "new",
+    "null",
+    "number",
+    "object",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "string",
+    "super",
+    "switch",
+    "symbol",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "undefined",
+    "unknown",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+];
+
+/// Standard JavaScript and TypeScript globals the generated runtime or its
+/// consumers rely on, declaring them would shadow them for the whole module.
+pub const GLOBALS: &[&str] = &[
+    "Array",
+    "ArrayBuffer",
+    "Awaited",
+    "BigInt",
+    "Blob",
+    "Boolean",
+    "Date",
+    "Error",
+    "Exclude",
+    "Extract",
+    "File",
+    "FormData",
+    "Function",
+    "Headers",
+    "JSON",
+    "Map",
+    "Math",
+    "NonNullable",
+    "Number",
+    "Object",
+    "Omit",
+    "Partial",
+    "Pick",
+    "Promise",
+    "Readonly",
+    "Record",
+    "RegExp",
+    "Request",
+    "RequestInit",
+    "Required",
+    "Response",
+    "ReturnType",
+    "Set",
+    "String",
+    "Symbol",
+    "TypeError",
+    "URL",
+    "URLSearchParams",
+    "Uint8Array",
+    "console",
+    "encodeURIComponent",
+    "fetch",
+];
+
+pub fn create_input_type_name_from_path(path: &str, prefix: Option<&str>) -> String {
+    let clear = path.replace('/', "_").replace(['{', '}'], "");
     let mut result = String::new();
-    result.push_str(format!("{}", prefix.unwrap_or(&String::new())).as_str());
+    result.push_str(prefix.unwrap_or_default());
     result.push_str(&clear);
     result.push_str("_Params");
+    identifier(&result)
+}
+
+pub fn create_webhook_type_name(name: &str) -> String {
+    identifier(&format!("{}_webhook", name))
+}
+
+/// Method + path based function name, e.g. `get_apps_by_machines_by` for
+/// `GET /apps/{app_name}/machines/{id}`.
+pub fn create_fn_name_from_path(method: &str, path: &str) -> String {
+    let mut fn_name = method.to_lowercase();
+    fn_name.push('_');
+
+    path.split('/').filter(|&x| !x.is_empty()).for_each(|x| {
+        if x.starts_with('{') && x.ends_with('}') {
+            fn_name.push_str("by_");
+        } else {
+            fn_name.push_str(x);
+            fn_name.push('_');
+        }
+    });
+
+    if fn_name.ends_with('_') {
+        fn_name = fn_name.trim_end_matches('_').to_string();
+    }
+
+    identifier(&fn_name)
+}
+
+/// Emitted name of a type, e.g. a definition or an operation's `_response`.
+pub fn type_name(name: &str, options: &Options) -> String {
+    identifier(&options.type_case.apply(name))
+}
+
+/// Emitted name of a function.
+pub fn function_name(name: &str, options: &Options) -> String {
+    identifier(&options.function_case.apply(name))
+}
+
+/// Emitted key of a property or parameter, quoted when needed.
+pub fn property_name(name: &str, options: &Options) -> String {
+    property_key(&options.property_case.apply(name))
+}
+
+/// Quotes names that aren't valid identifiers (e.g. `X-Request-Id`) so they
+/// can be used as object keys.
+pub fn property_key(name: &str) -> String {
+    match is_identifier(name) {
+        true => name.to_string(),
+        false => string_literal(name),
+    }
+}
+
+/// Single-quoted string literal, e.g. for enum values.
+pub fn string_literal(value: &str) -> String {
+    format!(
+        "'{}'",
+        value
+            .replace('\\', "\\\\")
+            .replace('\'', "\\'")
+            .replace('\n', "\\n")
+    )
+}
+
+/// Turns any spec name (e.g. `list-apps.v2`, `Foo Bar@1`, `default`) into a
+/// valid identifier. Invalid characters become `_`, names starting with a
+/// digit get a `_` prefix and reserved words a `_` suffix.
+pub fn identifier(name: &str) -> String {
+    let mut result: String = name
+        .chars()
+        .map(|c| match is_identifier_char(c) {
+            true => c,
+            false => '_',
+        })
+        .collect();
+
+    if !result.starts_with(|c: char| is_identifier_char(c) && !c.is_ascii_digit()) {
+        result.insert(0, '_');
+    }
+
+    if RESERVED_WORDS.contains(&result.as_str()) {
+        result.push('_');
+    }
+
     result
 }
+
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(is_identifier_char)
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '$'
+}
+
+/// Tracks emitted identifiers and the spec location each one came from, so
+/// two spec names never end up declaring the same identifier.
+pub struct Identifiers {
+    taken: HashMap<String, String>,
+    separator: &'static str,
+}
+
+impl Identifiers {
+    /// `reserved` are names already declared by the generated runtime.
+    pub fn new(reserved: &[&str]) -> Identifiers {
+        Identifiers {
+            taken: reserved
+                .iter()
+                .map(|name| (name.to_string(), "the generated runtime".to_string()))
+                .collect(),
+            separator: "_",
+        }
+    }
+
+    /// Suffixes names with `separator` instead of `_`, e.g. `Cat2` for
+    /// identifiers that have to stay PascalCase.
+    pub fn separated_by(self, separator: &'static str) -> Identifiers {
+        Identifiers { separator, ..self }
+    }
+
+    /// Where `name` was declared, if it is taken.
+    pub fn owner(&self, name: &str) -> Option<&str> {
+        self.taken.get(name).map(String::as_str)
+    }
+
+    pub fn insert(&mut self, name: &str, location: &str) {
+        self.taken.insert(name.to_string(), location.to_string());
+    }
+
+    /// Claims `name` for `location`, adding the first free `_2`, `_3`, ...
+    /// suffix when it is already taken.
+    pub fn claim(&mut self, name: &str, location: &str) -> String {
+        let mut candidate = name.to_string();
+        let mut suffix = 2;
+
+        while self.owner(&candidate).is_some() {
+            candidate = format!("{}{}{}", name, self.separator, suffix);
+            suffix += 1;
+        }
+
+        self.report(name, location, &candidate);
+        self.insert(&candidate, location);
+        candidate
+    }
+
+    /// Warns when `location` wanted `name` but had to use `used` instead.
+    pub fn report(&self, name: &str, location: &str, used: &str) {
+        if let Some(owner) = self.owner(name).filter(|_| name != used) {
+            warn!(
+                "{} and {} both generate `{}`, using `{}` for {}",
+                owner, location, name, used, location
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn quote_invalid_property_keys() {
+        assert_eq!(property_key("page_size"), "page_size");
+        assert_eq!(property_key("X-Request-Id"), "'X-Request-Id'");
+        assert_eq!(property_key("1st"), "'1st'");
+    }
+
+    #[test]
+    fn sanitize_identifiers() {
+        assert_eq!(identifier("listApps"), "listApps");
+        assert_eq!(identifier("list-apps.v2"), "list_apps_v2");
+        assert_eq!(identifier("2fa_verify"), "_2fa_verify");
+        assert_eq!(identifier("Foo Bar@v1+beta"), "Foo_Bar_v1_beta");
+        assert_eq!(identifier("default"), "default_");
+        assert_eq!(identifier("string"), "string_");
+        assert_eq!(identifier(""), "_");
+    }
+
+    #[test]
+    fn create_names_from_paths() {
+        assert_eq!(
+            create_fn_name_from_path("GET", "/apps/{app_name}/machines/{id}"),
+            "get_apps_by_machines_by"
+        );
+        assert_eq!(
+            create_input_type_name_from_path("/v1/user-groups/{id}.json", Some("post")),
+            "post_v1_user_groups_id_json_Params"
+        );
+        assert_eq!(create_webhook_type_name("new pet"), "new_pet_webhook");
+    }
+
+    #[test]
+    fn suffix_colliding_identifiers() {
+        let mut identifiers = Identifiers::new(&["Params"]);
+
+        assert_eq!(
+            identifiers.claim("foo_bar", "#/definitions/foo.bar"),
+            "foo_bar"
+        );
+        assert_eq!(
+            identifiers.claim("foo_bar", "#/definitions/foo_bar"),
+            "foo_bar_2"
+        );
+        assert_eq!(
+            identifiers.claim("foo_bar", "#/definitions/foo-bar"),
+            "foo_bar_3"
+        );
+        assert_eq!(
+            identifiers.claim("Params", "#/definitions/Params"),
+            "Params_2"
+        );
+        assert_eq!(
+            identifiers.owner("foo_bar_2"),
+            Some("#/definitions/foo_bar")
+        );
+
+        let mut variants = Identifiers::new(&[]).separated_by("");
+        assert_eq!(variants.claim("HotDog", "hot-dog"), "HotDog");
+        assert_eq!(variants.claim("HotDog", "hot_dog"), "HotDog2");
+    }
+}
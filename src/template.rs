@@ -1,321 +1,1562 @@
-use std::collections::HashMap;
-use std::collections::HashSet;
+use anyhow::{bail, Result};
+use log::warn;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 
-use crate::parser;
+use crate::generator::{ordered, ordered_operations, ordered_properties, Generator, OutputFile};
+use crate::ir;
+use crate::options::Options;
 use crate::sanitizer;
 
-pub fn generate_file_lines(schema: parser::Schema) -> Vec<String> {
-    let mut lines: Vec<String> = Vec::new();
+/// Types and values declared by the generated runtime.
+const RUNTIME_TYPES: &[&str] = &[
+    "ParamRecord",
+    "ParamFormat",
+    "Params",
+    "BodyEncoding",
+    "ResponseEncoding",
+    "Shape",
+];
+const RUNTIME_VALUES: &[&str] = &[
+    "shapes",
+    "operationShapes",
+    "resolveShape",
+    "renameKeys",
+    "delimiters",
+    "serializeParam",
+    "createUrl",
+    "createHeaders",
+    "encodeBody",
+    "fieldValue",
+    "readBody",
+    "fetcher",
+    "mutator",
+];
+
+/// Identifiers emitted so far, types and functions live in separate
+/// namespaces.
+struct Declared {
+    types: sanitizer::Identifiers,
+    functions: sanitizer::Identifiers,
+    /// Zod schemas are values named like their types, so type names are
+    /// claimed in both namespaces.
+    schemas: bool,
+}
 
-    generate_baselines(&schema, &mut lines);
-    generate_definition_types(&schema, &mut lines);
+impl Declared {
+    fn new(declarations: &impl Declarations) -> Declared {
+        let reserved = declarations.reserved();
 
-    schema.paths.iter().for_each(|(key, value)| {
-        match &value.get {
-            Some(_path) => generate_fetcher(key, _path.clone(), &mut lines),
-            None => (),
+        match declarations.declares_schemas() {
+            true => {
+                let reserved =
+                    [RUNTIME_TYPES, RUNTIME_VALUES, sanitizer::GLOBALS, reserved].concat();
+
+                Declared {
+                    types: sanitizer::Identifiers::new(&reserved),
+                    functions: sanitizer::Identifiers::new(&reserved),
+                    schemas: true,
+                }
+            }
+            false => Declared {
+                types: sanitizer::Identifiers::new(
+                    &[RUNTIME_TYPES, sanitizer::GLOBALS, reserved].concat(),
+                ),
+                functions: sanitizer::Identifiers::new(
+                    &[RUNTIME_VALUES, sanitizer::GLOBALS, reserved].concat(),
+                ),
+                schemas: false,
+            },
         }
+    }
 
-        match &value.post {
-            Some(_path) => generate_mutator(key, &"POST".to_string(), _path.clone(), &mut lines),
-            None => (),
+    fn type_owner(&self, name: &str) -> Option<&str> {
+        match self.schemas {
+            true => self.types.owner(name).or(self.functions.owner(name)),
+            false => self.types.owner(name),
         }
+    }
 
-        match &value.put {
-            Some(_path) => generate_mutator(key, &"PUT".to_string(), _path.clone(), &mut lines),
-            None => (),
+    fn insert_type(&mut self, name: &str, location: &str) {
+        self.types.insert(name, location);
+
+        if self.schemas {
+            self.functions.insert(name, location);
         }
-        match &value.delete {
-            Some(_path) => generate_mutator(key, &"DELETE".to_string(), _path.clone(), &mut lines),
-            None => (),
+    }
+
+    fn report_type(&self, name: &str, location: &str, used: &str) {
+        match self.types.owner(name) {
+            Some(_) => self.types.report(name, location, used),
+            None => self.functions.report(name, location, used),
         }
-        match &value.patch {
-            Some(_path) => generate_mutator(key, &"PATCH".to_string(), _path.clone(), &mut lines),
-            None => (),
+    }
+
+    fn claim_type(&mut self, name: &str, location: &str) -> String {
+        let used = self.types.claim(name, location);
+
+        if self.schemas {
+            self.functions.insert(&used, location);
         }
-    });
 
-    return lines;
+        used
+    }
 }
 
-fn generate_fn_name(method: String, path: String) -> String {
-    let mut fn_name = method.to_lowercase();
-    fn_name.push_str("_");
+/// How a TypeScript target declares the types of definitions, webhooks and
+/// operations. The runtime, the generated functions and their names are
+/// shared.
+pub trait Declarations {
+    /// Lines emitted before the runtime.
+    fn header(&self) -> &'static str {
+        ""
+    }
+
+    /// Names taken by the declarations themselves.
+    fn reserved(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Whether every type is also declared as a value of the same name.
+    fn declares_schemas(&self) -> bool {
+        false
+    }
+
+    /// Type of the schema `fetcher` and `mutator` parse successful responses
+    /// through, `None` when responses aren't validated.
+    fn response_schema(&self, _options: &Options) -> Option<&'static str> {
+        None
+    }
+
+    /// Declaration of the named type `name`. `recursive` types reference
+    /// themselves.
+    fn declare_definition(
+        &self,
+        name: &str,
+        ty: &ir::Type,
+        recursive: bool,
+        options: &Options,
+    ) -> String;
+
+    /// Declaration of a type accepting any of `members`.
+    fn declare_union(&self, name: &str, members: Vec<&ir::Type>, options: &Options) -> String;
+
+    /// Declaration of the parameters of `op`, grouped by location.
+    fn declare_params(&self, name: &str, op: &ir::Operation, options: &Options) -> String;
 
-    path.split("/").filter(|&x| !x.is_empty()).for_each(|x| {
-        if x.starts_with("{") && x.ends_with("}") {
-            fn_name.push_str("by_");
-        } else {
-            fn_name.push_str(x);
-            fn_name.push_str("_");
+    /// Declarations of the successful and error responses of `op`.
+    fn declare_responses(
+        &self,
+        op: &ir::Operation,
+        response: &str,
+        error: &str,
+        options: &Options,
+    ) -> Vec<String>;
+
+    /// Declaration of the body of `op`, `never` when it has none.
+    fn declare_body(&self, name: &str, body: Option<&ir::Body>, options: &Options) -> String;
+}
+
+/// Types and `fetch` based functions in a single TypeScript file.
+pub struct TypeScript;
+
+impl Generator for TypeScript {
+    fn generate(&self, api: ir::Api, options: &Options, path: &Path) -> Result<Vec<OutputFile>> {
+        if options.validate_responses {
+            warn!("validate_responses requires the zod target, responses are not validated");
+        }
+
+        generate_file(api, options, path, self)
+    }
+}
+
+impl Declarations for TypeScript {
+    fn declare_definition(
+        &self,
+        name: &str,
+        ty: &ir::Type,
+        _recursive: bool,
+        options: &Options,
+    ) -> String {
+        format!(
+            "export type {} = {};\n",
+            name,
+            parse_definition(ty, options)
+        )
+    }
+
+    fn declare_union(&self, name: &str, members: Vec<&ir::Type>, options: &Options) -> String {
+        let types = members
+            .into_iter()
+            .map(|ty| parse_type(ty, options, "unknown"))
+            .fold(Vec::new(), |mut acc, x| {
+                if !acc.contains(&x) {
+                    acc.push(x);
+                }
+                acc
+            });
+
+        format!(
+            "export type {} = {};\n",
+            name,
+            if types.is_empty() {
+                "never".to_string()
+            } else {
+                types.join(" | ")
+            }
+        )
+    }
+
+    fn declare_params(&self, name: &str, op: &ir::Operation, options: &Options) -> String {
+        let mut fields = vec![
+            format!(
+                "query: {{{}}}",
+                parse_params(op, ir::ParameterLocation::Query, options)
+            ),
+            format!(
+                "path: {{{}}}",
+                parse_params(op, ir::ParameterLocation::Path, options)
+            ),
+        ];
+
+        let header_type = parse_params(op, ir::ParameterLocation::Header, options);
+        if !header_type.is_empty() {
+            fields.push(format!("header: {{{header_type}}}"));
+        }
+
+        let cookie_type = parse_params(op, ir::ParameterLocation::Cookie, options);
+        if !cookie_type.is_empty() {
+            fields.push(format!("cookie: {{{cookie_type}}}"));
         }
-    });
 
-    if fn_name.ends_with("_") {
-        fn_name = fn_name.trim_end_matches('_').to_string();
+        format!("type {name} = {{ {} }};\n", fields.join(", "))
     }
 
-    fn_name.replace("-", "_")
+    fn declare_responses(
+        &self,
+        op: &ir::Operation,
+        response: &str,
+        error: &str,
+        options: &Options,
+    ) -> Vec<String> {
+        let error_types = response_types(op, &["4", "5"], options);
+
+        vec![
+            format!(
+                "type {} = {};\n",
+                response,
+                response_types(op, &["2"], options).join(" | ")
+            ),
+            format!(
+                "type {} = {};\n",
+                error,
+                if error_types.is_empty() {
+                    "never".to_string()
+                } else {
+                    error_types.join(" | ")
+                }
+            ),
+        ]
+    }
+
+    fn declare_body(&self, name: &str, body: Option<&ir::Body>, options: &Options) -> String {
+        let body_type = match body {
+            Some(body) => parse_body(body, options),
+            None => "never".to_string(),
+        };
+
+        format!("type {name}= {body_type};\n")
+    }
+}
+
+/// The whole TypeScript file, with its types declared by `declarations`.
+pub fn generate_file(
+    api: ir::Api,
+    options: &Options,
+    path: &Path,
+    declarations: &impl Declarations,
+) -> Result<Vec<OutputFile>> {
+    check_renames(&api, options)?;
+
+    Ok(vec![OutputFile {
+        path: path.to_path_buf(),
+        contents: generate_file_lines(api, options, declarations).concat(),
+    }])
+}
+
+fn generate_file_lines(
+    mut api: ir::Api,
+    options: &Options,
+    declarations: &impl Declarations,
+) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut declared = Declared::new(declarations);
+
+    let renames = claim_type_names(&api, options, &mut declared);
+    api.rename_types(&renames);
+
+    let header = declarations.header();
+    if !header.is_empty() {
+        lines.push(header.to_string());
+    }
+
+    generate_baselines(&api, options, declarations, &mut lines);
+    generate_definition_types(&api, options, declarations, &mut lines);
+    generate_webhook_types(&api, options, declarations, &mut declared, &mut lines);
+
+    ordered_operations(&api.operations, options)
+        .into_iter()
+        .for_each(|op| match op.method {
+            ir::Method::Get => {
+                generate_fetcher(op, options, declarations, &mut declared, &mut lines)
+            }
+            _ => generate_mutator(op, options, declarations, &mut declared, &mut lines),
+        });
+
+    lines
+}
+
+/// Claims a name for every named type. Types whose name is already taken get
+/// a suffixed name, returned as old -> new.
+fn claim_type_names(
+    api: &ir::Api,
+    options: &Options,
+    declared: &mut Declared,
+) -> HashMap<String, String> {
+    let mut renames = HashMap::new();
+
+    ordered(&api.types, options)
+        .into_iter()
+        .for_each(|(key, _)| {
+            let location = format!("schema {key}");
+            let name = sanitizer::type_name(key, options);
+
+            let renamed = std::iter::once(key.clone())
+                .chain((2..).map(|n| format!("{key}_{n}")))
+                .find(|candidate| {
+                    (candidate == key || !api.types.contains_key(candidate))
+                        && declared
+                            .type_owner(&sanitizer::type_name(candidate, options))
+                            .is_none()
+                })
+                .unwrap();
+            let used = sanitizer::type_name(&renamed, options);
+
+            declared.report_type(&name, &location, &used);
+            declared.insert_type(&used, &location);
+
+            if renamed != *key {
+                renames.insert(key.clone(), renamed);
+            }
+        });
+
+    renames
+}
+
+/// Names emitted for an operation and its types.
+struct OperationNames {
+    function: String,
+    params: String,
+    response: String,
+    error: String,
+    body: String,
+}
+
+impl OperationNames {
+    fn is_free(&self, declared: &Declared) -> bool {
+        declared.functions.owner(&self.function).is_none()
+            && self.types().all(|name| declared.type_owner(name).is_none())
+    }
+
+    fn types(&self) -> impl Iterator<Item = &String> {
+        [&self.params, &self.response, &self.error, &self.body].into_iter()
+    }
+}
+
+/// Names the operation and claims the names, adding a `_2`, `_3`, ... suffix
+/// when any of them is already declared.
+fn claim_operation_names(
+    op: &ir::Operation,
+    options: &Options,
+    declared: &mut Declared,
+) -> OperationNames {
+    let location = format!("{} {}", op.method.as_str(), op.path);
+    let preferred = operation_names(op, options, 1);
+
+    let names = (1..)
+        .map(|suffix| operation_names(op, options, suffix))
+        .find(|names| names.is_free(declared))
+        .unwrap();
+
+    // Reports the first colliding name only, the others follow the same suffix.
+    match declared.functions.owner(&preferred.function) {
+        Some(_) => declared
+            .functions
+            .report(&preferred.function, &location, &names.function),
+        None => {
+            if let Some((name, used)) = preferred
+                .types()
+                .zip(names.types())
+                .find(|(name, _)| declared.type_owner(name).is_some())
+            {
+                declared.report_type(name, &location, used);
+            }
+        }
+    }
+
+    declared.functions.insert(&names.function, &location);
+    names
+        .types()
+        .for_each(|name| declared.insert_type(name, &location));
+
+    names
+}
+
+/// Names an operation after its sanitized `operationId` when enabled, after
+/// its method and path otherwise. A `suffix` above 1 is appended to
+/// disambiguate colliding operations.
+fn operation_names(op: &ir::Operation, options: &Options, suffix: usize) -> OperationNames {
+    let (key, method) = (op.path.as_str(), op.method.as_str());
+
+    let operation_id = match options.use_operation_id {
+        true => op.operation_id.as_deref().map(sanitizer::identifier),
+        false => None,
+    };
+
+    let (base, function, params) = match operation_id {
+        Some(name) => (name.clone(), name.clone(), format!("{name}_Params")),
+        None => {
+            let fn_name = sanitizer::create_fn_name_from_path(method, key);
+
+            match method {
+                // Fetchers have always been exported with a `get_` prefix.
+                "GET" => (
+                    fn_name.clone(),
+                    format!("get_{fn_name}"),
+                    sanitizer::create_input_type_name_from_path(key, None),
+                ),
+                _ => (
+                    fn_name.clone(),
+                    fn_name,
+                    sanitizer::create_input_type_name_from_path(key, Some(&method.to_lowercase())),
+                ),
+            }
+        }
+    };
+
+    let (base, function, params) = match suffix {
+        1 => (base, function, params),
+        _ => (
+            format!("{base}_{suffix}"),
+            format!("{function}_{suffix}"),
+            match params.strip_suffix("_Params") {
+                Some(stem) => format!("{stem}_{suffix}_Params"),
+                None => format!("{params}_{suffix}"),
+            },
+        ),
+    };
+
+    let type_name = |suffix: &str| sanitizer::type_name(&format!("{base}_{suffix}"), options);
+
+    OperationNames {
+        function: sanitizer::function_name(&function, options),
+        params: sanitizer::type_name(&params, options),
+        response: type_name("response"),
+        error: type_name("error"),
+        body: type_name("body"),
+    }
 }
 
-fn generate_baselines(schema: &parser::Schema, lines: &mut Vec<String>) {
-    let mut prefix = String::new();
-    prefix.push_str("https://");
-    prefix.push_str(&schema.host);
-    prefix.push_str(&schema.base_path);
+fn generate_baselines(
+    api: &ir::Api,
+    options: &Options,
+    declarations: &impl Declarations,
+    lines: &mut Vec<String>,
+) {
+    let prefix = &api.base_url;
+    // Relative base URLs only resolve in a browser, against the current page.
+    let relative_to = match url::Url::parse(prefix) {
+        Ok(_) => "",
+        Err(_) => ", (globalThis as { location?: { href: string } }).location?.href",
+    };
+
+    // Renamed properties and parameters are mapped back to their wire names
+    // around each request. Nothing is mapped when `property_case` renames
+    // nothing.
+    let (renaming, fetcher_shape, mutator_shape, request_body, response_body) =
+        match generate_shapes(api, options) {
+            Some((shapes, operation_shapes)) => (
+                generate_renaming(&shapes, &operation_shapes),
+                "\n\tconst shape = operationShapes[`GET ${url}`] ?? {};\n\tparams = renameKeys(params, shape.params, true) as Params;",
+                "\n\tconst shape = operationShapes[`${method} ${url}`] ?? {};\n\tparams = renameKeys(params, shape.params, true) as Params;",
+                "renameKeys(body, shape.body, true)",
+                "renameKeys(await readBody(res, responseEncoding), res.ok ? shape.response : shape.error, false)",
+            ),
+            None => (String::new(), "", "", "body", "await readBody(res, responseEncoding)"),
+        };
+
+    // Successful responses are parsed through the schema passed by the
+    // generated functions.
+    let (schema_param, result) = match declarations.response_schema(options) {
+        Some(schema) => (
+            format!("\n\tschema?: {schema},"),
+            "schema ? schema.parse(bodyData) : (bodyData as TResult)",
+        ),
+        None => (String::new(), "bodyData as TResult"),
+    };
 
     lines.push(format!(
         r#"
-type Record = {{ [key: string]: string | number | boolean | undefined }};
-type Params = {{ query: Record; path: Record }};
+type ParamRecord = {{ [key: string]: unknown }};
+type ParamFormat = "csv" | "ssv" | "tsv" | "pipes" | "multi" | "form" | "deepObject";
+type Params = {{
+	query: ParamRecord;
+	path: ParamRecord;
+	header?: ParamRecord;
+	cookie?: ParamRecord;
+	formats?: {{ [key: string]: ParamFormat }};
+}};
+type BodyEncoding = "json" | "form" | "multipart";
+type ResponseEncoding = "json" | "text" | "blob";
+{renaming}
+const delimiters: {{ [format: string]: string }} = {{ csv: ",", ssv: " ", tsv: "\t", pipes: "|" }};
+
+function serializeParam(key: string, value: unknown, format: ParamFormat = "csv"): [string, string][] {{
+	if (value === undefined || value === null) {{
+		return [];
+	}}
+
+	if (Array.isArray(value)) {{
+		if (format === "multi" || format === "form") {{
+			return value.map((item) => [key, String(item)]);
+		}}
+
+		return [[key, value.map(String).join(delimiters[format] ?? ",")]];
+	}}
+
+	if (typeof value === "object") {{
+		const entries = Object.entries(value).filter(([, item]) => item !== undefined && item !== null);
+
+		if (format === "deepObject") {{
+			return entries.map(([prop, item]) => [`${{key}}[${{prop}}]`, String(item)]);
+		}}
+
+		if (format === "form" || format === "multi") {{
+			return entries.map(([prop, item]) => [prop, String(item)]);
+		}}
+
+		return [[key, entries.flat().map(String).join(delimiters[format] ?? ",")]];
+	}}
+
+	return [[key, String(value)]];
+}}
 
 function createUrl(url: string, params: Params) {{
-	const _url = Object.keys(params || {{}}).reduce(
-		(acc, key) => acc.replace(`{{${{key}}}}`, params[key]),
-		url,
-	);
+	const formats = params.formats ?? {{}};
+	const _url = Object.keys(params.path || {{}}).reduce((acc, key) => {{
+		const value = serializeParam(key, params.path[key], formats[key])[0]?.[1] ?? "";
+		return acc.replace(`{{${{key}}}}`, encodeURIComponent(value));
+	}}, url);
 
-	const completeUrl = new URL("{prefix}" + _url);
+	const completeUrl = new URL("{prefix}" + _url{relative_to});
 	Object.keys(params.query).forEach((key) => {{
-		completeUrl.searchParams.append(key, params.query[key]?.toString() ?? "");
+		serializeParam(key, params.query[key], formats[key]).forEach(([name, value]) => {{
+			completeUrl.searchParams.append(name, value);
+		}});
 	}});
 
 	return completeUrl;
 }}
 
-async function fetcher<TResult, TErr>(
-  url: string,
-  params: {{ query: Record; path: Record }},
-  init?: RequestInit,
-) {{
-
-const _init = init ? {{ ...init, method: "GET" }} : {{ method: "GET" }};
-const _url = Object.keys(params || {{}}).reduce(
-	(acc, key) => acc.replace(`{{$key}}`, params[key]),
-	url,
-);
-
-const completeUrl = new URL(_url);
-Object.keys(params.query).forEach((key) => {{
-	const val = params.query[key];
-	if (!val) {{
-		return;
+function createHeaders(params: Params, init?: RequestInit) {{
+	const headers = new Headers(init?.headers);
+	Object.entries(params.header ?? {{}}).forEach(([key, value]) => {{
+		if (value !== undefined) {{
+			headers.set(key, String(value));
+		}}
+	}});
+
+	const cookies = Object.entries(params.cookie ?? {{}})
+		.filter(([, value]) => value !== undefined)
+		.map(([key, value]) => `${{key}}=${{encodeURIComponent(String(value))}}`);
+	if (cookies.length > 0) {{
+		headers.append("Cookie", cookies.join("; "));
+	}}
+
+	return headers;
+}}
+
+function encodeBody(body: unknown, encoding: BodyEncoding, headers: Headers) {{
+	if (body === null || body === undefined) {{
+		return undefined;
+	}}
+
+	if (encoding === "json") {{
+		headers.set("Content-Type", "application/json");
+		return JSON.stringify(body);
+	}}
+
+	const fields = Object.entries(body as {{ [key: string]: unknown }})
+		.filter(([, value]) => value !== undefined)
+		.flatMap(([key, value]) => (Array.isArray(value) ? value : [value]).map((item) => [key, item] as const));
+
+	if (encoding === "form") {{
+		const form = new URLSearchParams();
+		fields.forEach(([key, value]) => form.append(key, fieldValue(value)));
+		return form;
 	}}
 
-	completeUrl.searchParams.append(key, val.toString());
-}});
+	const form = new FormData();
+	fields.forEach(([key, value]) => form.append(key, value instanceof Blob ? value : fieldValue(value)));
+	return form;
+}}
+
+// Objects in form fields are sent as JSON under the name of the field.
+function fieldValue(value: unknown) {{
+	return value !== null && typeof value === "object" ? JSON.stringify(value) : String(value);
+}}
 
-  const res = await fetch(completeUrl, _init);
-  const bodyData = await res.json();
+// HEAD requests and preflight responses come back without a body. Error
+// bodies are read as JSON whatever the successful responses are.
+async function readBody(res: Response, encoding: ResponseEncoding) {{
+	if (res.ok && encoding === "blob") {{
+		return res.blob();
+	}}
 
-  if (!res.ok) {{
-    return bodyData as TErr;
-  }}
+	const text = await res.text();
 
-  return bodyData as TResult;
+	if (res.ok && encoding === "text") {{
+		return text;
+	}}
+
+	return text ? JSON.parse(text) : undefined;
+}}
+
+async function fetcher<TResult, TErr>(
+	url: string,
+	params: Params,
+	init?: RequestInit,
+	responseEncoding: ResponseEncoding = "json",{schema_param}
+) {{{fetcher_shape}
+	const _init = {{ ...init, method: "GET", headers: createHeaders(params, init) }};
+
+	const res = await fetch(createUrl(url, params), _init);
+	const bodyData = {response_body};
+
+	if (!res.ok) {{
+		return bodyData as TErr;
+	}}
+
+	return {result};
 }}
 
 
 async function mutator<TBody, TResult, TErr>(
-	method: "POST" | "PUT" | "DELETE" | "PATCH",
+	method: "POST" | "PUT" | "DELETE" | "PATCH" | "HEAD" | "OPTIONS" | "TRACE",
 	url: string,
 	params: Params,
 	body: TBody | null,
 	init?: RequestInit,
-) {{
+	encoding: BodyEncoding = "json",
+	responseEncoding: ResponseEncoding = "json",{schema_param}
+) {{{mutator_shape}
+	const headers = createHeaders(params, init);
 	const _init = Object.assign(init ?? {{}}, {{
 		method,
-		body: body ? JSON.stringify(body) : undefined,
+		headers,
+		body: encodeBody({request_body}, encoding, headers),
 	}});
 
 	const res = await fetch(createUrl(url, params), _init);
-	const bodyData = await res.json();
+	const bodyData = {response_body};
 
 	if (!res.ok) {{
 		return bodyData as TErr;
 	}}
 
-	return bodyData as TResult;
+	return {result};
 }}
 
 "#
     ));
 }
 
-fn generate_definition_types(schema: &parser::Schema, lines: &mut Vec<String>) {
-    let mut result: HashMap<String, String> = HashMap::new();
+/// Runtime renaming properties and parameters inside a value, with the
+/// `shapes` of named types and the `operationShapes` of each operation.
+fn generate_renaming(shapes: &str, operation_shapes: &str) -> String {
+    format!(
+        r#"
+// Properties to rename inside a value: declared properties (generated name ->
+// [wire name, shape of the value]), array items, map values, the members of a
+// union or intersection, or the name of a type in `shapes`. The parameters of
+// an operation are renamed through the shape of their `Params`.
+type Shape =
+	| string
+	| {{ props?: {{ [name: string]: [string, Shape?] }}; items?: Shape; values?: Shape; all?: Shape[] }};
+const shapes: {{ [name: string]: Shape }} = {shapes};
+const operationShapes: {{ [operation: string]: {{ params?: Shape; body?: Shape; response?: Shape; error?: Shape }} }} = {operation_shapes};
+
+function resolveShape(shape: Shape | undefined): Exclude<Shape, string> | undefined {{
+	if (typeof shape === "string") {{
+		return resolveShape(shapes[shape]);
+	}}
 
-    schema.definitions.iter().for_each(|(key, value)| {
-        let parsed_key = parser::normalize_key(key);
+	if (shape?.all === undefined) {{
+		return shape;
+	}}
 
-        let raw_type = if value._enum.is_some() {
-            parser::parse_enum(value)
-        } else {
-            parser::create_raw_type_from_properties(&value.properties.clone().unwrap_or_default())
-        };
+	return shape.all.map(resolveShape).reduce<Exclude<Shape, string>>(
+		(acc, member) => ({{
+			props: {{ ...acc.props, ...member?.props }},
+			items: acc.items ?? member?.items,
+			values: acc.values ?? member?.values,
+		}}),
+		{{}},
+	);
+}}
 
-        result.insert(parsed_key, raw_type);
-    });
+// Only declared properties are renamed, keys of maps are kept as they are.
+function renameKeys(value: unknown, shape: Shape | undefined, toWire: boolean): unknown {{
+	const resolved = resolveShape(shape);
 
-    // sort the keys from the hashmap
-    let mut keys: Vec<&String> = result.keys().collect();
-    keys.sort();
+	if (resolved === undefined || value === null || typeof value !== "object" || value instanceof Blob) {{
+		return value;
+	}}
 
-    keys.iter().for_each(|key| {
-        lines.push(format!(
-            "export type {} = {};\n",
-            key,
-            result.get(*key).unwrap()
-        ));
-    });
+	if (Array.isArray(value)) {{
+		return value.map((item) => renameKeys(item, resolved.items, toWire));
+	}}
+
+	const props = Object.entries(resolved.props ?? {{}});
+
+	return Object.fromEntries(
+		Object.entries(value).map(([key, item]) => {{
+			const prop = props.find(([local, [wire]]) => (toWire ? local : wire) === key);
+
+			if (prop === undefined) {{
+				return [key, renameKeys(item, resolved.values, toWire)];
+			}}
+
+			const [local, [wire, propShape]] = prop;
+			return [toWire ? wire : local, renameKeys(item, propShape, toWire)];
+		}}),
+	);
+}}
+"#
+    )
+}
+
+/// Fails when `property_case` gives two properties of an object, or two
+/// parameters of an operation in the same location, the same name. They
+/// couldn't be told apart when mapping them back to their wire names.
+fn check_renames(api: &ir::Api, options: &Options) -> Result<()> {
+    api.sibling_names()
+        .into_iter()
+        .try_for_each(|(location, names)| {
+            let mut locals: HashMap<String, &String> = HashMap::new();
+
+            names.iter().try_for_each(|name| {
+                let local = options.property_case.apply(name);
+
+                match locals.insert(local.clone(), name) {
+                    Some(other) if other != name => bail!(
+                        "{} and {} of {} are both named {} by property_case",
+                        other,
+                        name,
+                        location,
+                        local
+                    ),
+                    _ => Ok(()),
+                }
+            })
+        })
 }
 
-fn generate_fetcher(key: &String, op: parser::Operation, lines: &mut Vec<String>) {
-    let _2xx_responses = op
-        .responses
+/// Renamed properties of every named type and of each operation's parameters,
+/// body and responses, as `shapes` and `operationShapes`. `None` when
+/// `property_case` keeps every name.
+fn generate_shapes(api: &ir::Api, options: &Options) -> Option<(String, String)> {
+    let shapes = Shapes::new(api, options);
+
+    let types: Vec<String> = ordered(&api.types, options)
+        .into_iter()
+        .filter(|(key, _)| shapes.renamed.contains(*key))
+        .filter_map(|(key, ty)| Some(format!("{}: {}", js_string(key), shapes.shape(ty)?)))
+        .collect();
+
+    let operations: Vec<String> = api
+        .operations
         .iter()
-        .filter(|(key, _)| key.starts_with("2"))
-        .collect::<Vec<_>>();
-    let non_2xx_responses = op
-        .responses
+        .filter_map(|op| {
+            let responses =
+                |classes| shapes.all(op.responses_in(classes).filter_map(|res| res.ty.as_ref()));
+            let fields: Vec<String> = [
+                ("params", shapes.params(op)),
+                (
+                    "body",
+                    op.body.as_ref().and_then(|body| shapes.shape(&body.ty)),
+                ),
+                ("response", responses(&["2"])),
+                ("error", responses(&["4", "5"])),
+            ]
+            .into_iter()
+            .filter_map(|(field, shape)| Some(format!("{field}: {}", shape?)))
+            .collect();
+
+            match fields.is_empty() {
+                true => None,
+                false => Some(format!(
+                    "{}: {{ {} }}",
+                    js_string(&format!("{} {}", op.method.as_str(), op.path)),
+                    fields.join(", ")
+                )),
+            }
+        })
+        .collect();
+
+    if types.is_empty() && operations.is_empty() {
+        return None;
+    }
+
+    let object = |entries: Vec<String>| match entries.is_empty() {
+        true => "{}".to_string(),
+        false => format!("{{ {} }}", entries.join(", ")),
+    };
+
+    Some((object(types), object(operations)))
+}
+
+struct Shapes<'a> {
+    options: &'a Options,
+    /// Named types with a renamed property somewhere inside them.
+    renamed: HashSet<String>,
+}
+
+impl<'a> Shapes<'a> {
+    fn new(api: &ir::Api, options: &'a Options) -> Shapes<'a> {
+        let mut shapes = Shapes {
+            options,
+            renamed: HashSet::new(),
+        };
+
+        // A type is renamed when it references a renamed type, repeat until
+        // no more types are found.
+        loop {
+            let renamed: HashSet<String> = api
+                .types
+                .iter()
+                .filter(|(key, ty)| shapes.renamed.contains(*key) || shapes.renames(ty))
+                .map(|(key, _)| key.clone())
+                .collect();
+
+            if renamed.len() == shapes.renamed.len() {
+                return shapes;
+            }
+            shapes.renamed = renamed;
+        }
+    }
+
+    fn local_name(&self, name: &str) -> String {
+        self.options.property_case.apply(name)
+    }
+
+    fn renames(&self, ty: &ir::Type) -> bool {
+        let mut found = false;
+
+        ty.walk(&mut |ty| {
+            found |= match ty {
+                ir::Type::Object(object) => object
+                    .properties
+                    .iter()
+                    .any(|prop| self.local_name(&prop.name) != prop.name),
+                ir::Type::Variant { property, .. } => self.local_name(property) != *property,
+                ir::Type::Ref(name) => self.renamed.contains(name),
+                _ => false,
+            }
+        });
+
+        found
+    }
+
+    /// `Shape` of `ty`, `None` when nothing inside it is renamed.
+    fn shape(&self, ty: &ir::Type) -> Option<String> {
+        match ty {
+            ir::Type::Object(object) => {
+                let props: Vec<String> = object
+                    .properties
+                    .iter()
+                    .filter_map(|prop| self.prop(&prop.name, &prop.ty))
+                    .collect();
+
+                let mut fields = Vec::new();
+                if !props.is_empty() {
+                    fields.push(format!("props: {{ {} }}", props.join(", ")));
+                }
+                if let Some(values) = object.additional.as_deref().and_then(|ty| self.shape(ty)) {
+                    fields.push(format!("values: {values}"));
+                }
+
+                match fields.is_empty() {
+                    true => None,
+                    false => Some(format!("{{ {} }}", fields.join(", "))),
+                }
+            }
+            ir::Type::Array(items) => self
+                .shape(items)
+                .map(|items| format!("{{ items: {items} }}")),
+            ir::Type::Tuple { items, rest } => self
+                .all(items.iter().chain(rest.as_deref()))
+                .map(|items| format!("{{ items: {items} }}")),
+            ir::Type::Ref(name) => self.renamed.contains(name).then(|| js_string(name)),
+            ir::Type::Union(members) | ir::Type::Intersection(members) => self.all(members.iter()),
+            ir::Type::Variant { property, ty, .. } => {
+                let local = self.local_name(property);
+                let own = (local != *property).then(|| {
+                    format!(
+                        "{{ props: {{ {}: [{}] }} }}",
+                        js_string(&local),
+                        js_string(property)
+                    )
+                });
+
+                match (own, self.shape(ty)) {
+                    (Some(own), Some(inner)) => Some(format!("{{ all: [{inner}, {own}] }}")),
+                    (own, inner) => own.or(inner),
+                }
+            }
+            ir::Type::Nullable(inner) => self.shape(inner),
+            _ => None,
+        }
+    }
+
+    /// `"local": ["wire", shape]` entry of a property, `None` when neither it
+    /// nor anything inside it is renamed.
+    fn prop(&self, name: &str, ty: &ir::Type) -> Option<String> {
+        let local = self.local_name(name);
+        let wire = match self.shape(ty) {
+            Some(shape) => format!("{}, {shape}", js_string(name)),
+            None if local != name => js_string(name),
+            None => return None,
+        };
+
+        Some(format!("{}: [{wire}]", js_string(&local)))
+    }
+
+    /// Shape of the `Params` of `op`, renaming the parameters of each
+    /// location.
+    fn params(&self, op: &ir::Operation) -> Option<String> {
+        let locations: Vec<String> = [
+            ("query", ir::ParameterLocation::Query),
+            ("path", ir::ParameterLocation::Path),
+            ("header", ir::ParameterLocation::Header),
+            ("cookie", ir::ParameterLocation::Cookie),
+        ]
+        .into_iter()
+        .filter_map(|(field, location)| {
+            let props: Vec<String> = op
+                .parameters_in(location)
+                .filter_map(|param| self.prop(&param.name, &param.ty))
+                .collect();
+
+            match props.is_empty() {
+                true => None,
+                false => Some(format!(
+                    "{field}: [\"{field}\", {{ props: {{ {} }} }}]",
+                    props.join(", ")
+                )),
+            }
+        })
+        .collect();
+
+        match locations.is_empty() {
+            true => None,
+            false => Some(format!("{{ props: {{ {} }} }}", locations.join(", "))),
+        }
+    }
+
+    /// Shape of a value matching any or all of `members`.
+    fn all<'t>(&self, members: impl Iterator<Item = &'t ir::Type>) -> Option<String> {
+        let mut shapes: Vec<String> = members.filter_map(|member| self.shape(member)).collect();
+        shapes.dedup();
+
+        match shapes.len() {
+            0 => None,
+            1 => shapes.pop(),
+            _ => Some(format!("{{ all: [{}] }}", shapes.join(", "))),
+        }
+    }
+}
+
+fn js_string(value: &str) -> String {
+    serde_json::Value::String(value.to_string()).to_string()
+}
+
+fn generate_definition_types(
+    api: &ir::Api,
+    options: &Options,
+    declarations: &impl Declarations,
+    lines: &mut Vec<String>,
+) {
+    let recursive = api.recursive_types();
+
+    let mut result: Vec<(String, String)> = api
+        .types
         .iter()
-        .filter(|(key, _)| key.starts_with("4") || key.starts_with("5"))
-        .collect::<Vec<_>>();
+        .map(|(key, value)| {
+            let name = sanitizer::type_name(key, options);
+            let declaration =
+                declarations.declare_definition(&name, value, recursive.contains(key), options);
 
-    let query_type = op.parse_query();
-    let path_type = op.parse_path();
+            (name, declaration)
+        })
+        .collect();
 
-    let tmp_key = sanitizer::create_input_type_name_from_path(key, None);
-    let fn_name = generate_fn_name("get".to_string(), key.to_string());
+    // sort by the emitted names unless the spec order is kept
+    if !options.preserve_spec_order {
+        result.sort();
+    }
 
-    lines.push(format!("\n\n// Description - {}\n", op.description));
-    lines.push(format!(
-        "type {tmp_key} = {{ query: {{{query_type}}}, path: {{{path_type}}} }};\n"
-    ));
+    result
+        .into_iter()
+        .for_each(|(_, declaration)| lines.push(declaration));
+}
 
-    lines.push(format!(
-        "type {fn_name}_response = {};\n",
-        _2xx_responses
-            .iter()
-            .map(|(_, value)| value.parse_response())
-            .fold(HashSet::new(), |mut acc, x| {
-                acc.insert(x);
-                acc
-            })
+fn generate_webhook_types(
+    api: &ir::Api,
+    options: &Options,
+    declarations: &impl Declarations,
+    declared: &mut Declared,
+    lines: &mut Vec<String>,
+) {
+    ordered(&api.webhooks, options)
+        .into_iter()
+        .for_each(|(key, operations)| {
+            let name = declared.claim_type(
+                &sanitizer::type_name(&sanitizer::create_webhook_type_name(key), options),
+                &format!("webhook {key}"),
+            );
+            let bodies = operations
+                .iter()
+                .filter_map(|op| op.body.as_ref())
+                .map(|body| &body.ty);
+
+            lines.push(declarations.declare_union(&name, bodies.collect(), options));
+        });
+}
+
+/// Passes the serialization of array and object parameters along with the
+/// values, keyed by wire name as parameters are renamed before they are
+/// serialized.
+fn generate_props(op: &ir::Operation) -> String {
+    let formats = parse_formats(op);
+
+    if formats.is_empty() {
+        return "props".to_string();
+    }
+
+    format!(
+        "{{ ...props, formats: {{ {} }} }}",
+        formats
             .iter()
-            .map(|val| val.to_string())
+            .map(|(name, format)| { format!("{}: \"{}\"", sanitizer::property_key(name), format) })
             .collect::<Vec<_>>()
-            .join(" | ")
-    ));
+            .join(", ")
+    )
+}
+
+/// Distinct types of the responses in the given status classes, ordered by
+/// status.
+fn response_types(op: &ir::Operation, classes: &[&str], options: &Options) -> Vec<String> {
+    op.responses_in(classes)
+        .map(|response| parse_response(response, options))
+        .fold(Vec::new(), |mut acc, x| {
+            if !acc.contains(&x) {
+                acc.push(x);
+            }
+            acc
+        })
+}
+
+/// `// Description - ...` comment, continued on as many lines as the
+/// description has.
+fn description_comment(description: &str) -> String {
+    let lines: Vec<&str> = description.trim_end().lines().collect();
+
+    match lines.split_first() {
+        Some((first, rest)) => format!(
+            "\n\n// Description - {first}\n{}",
+            rest.iter()
+                .map(|line| format!("// {line}").trim_end().to_string() + "\n")
+                .collect::<String>()
+        ),
+        None => "\n\n// Description - \n".to_string(),
+    }
+}
+
+fn generate_fetcher(
+    op: &ir::Operation,
+    options: &Options,
+    declarations: &impl Declarations,
+    declared: &mut Declared,
+    lines: &mut Vec<String>,
+) {
+    let names = claim_operation_names(op, options, declared);
+    let key = &op.path;
+
+    lines.push(description_comment(&op.description));
+    lines.push(declarations.declare_params(&names.params, op, options));
+    lines.extend(declarations.declare_responses(op, &names.response, &names.error, options));
+
+    let props = generate_props(op);
+    let response_encoding = response_encoding(op);
+    let trailing = match (declarations.response_schema(options), response_encoding) {
+        (Some(_), _) => format!(", undefined, \"{response_encoding}\", {}", names.response),
+        (None, "json") => String::new(),
+        (None, _) => format!(", undefined, \"{response_encoding}\""),
+    };
+
     lines.push(format!(
-        "type {fn_name}_error = {};\n",
-        if non_2xx_responses.is_empty() {
-            "never".to_string()
-        } else {
-            non_2xx_responses
-                .iter()
-                .map(|(_, value)| value.parse_response())
-                .fold(HashSet::new(), |mut acc, x| {
-                    acc.insert(x);
-                    acc
-                })
-                .iter()
-                .map(|val| val.to_string())
-                .collect::<Vec<_>>()
-                .join(" | ")
-        }
+        r#"export async function {}(props: {}) {{
+    return fetcher<{}, {}>("{key}", {props}{trailing});
+}}
+"#,
+        names.function, names.params, names.response, names.error
     ));
+}
+
+fn generate_mutator(
+    op: &ir::Operation,
+    options: &Options,
+    declarations: &impl Declarations,
+    declared: &mut Declared,
+    lines: &mut Vec<String>,
+) {
+    let names = claim_operation_names(op, options, declared);
+    let (key, method) = (&op.path, op.method.as_str());
+
+    lines.push(description_comment(&op.description));
+    lines.push(declarations.declare_params(&names.params, op, options));
+    lines.extend(declarations.declare_responses(op, &names.response, &names.error, options));
+
+    let props = generate_props(op);
+    let OperationNames {
+        function,
+        params,
+        response,
+        error,
+        body: body_name,
+    } = names;
+
+    let encoding = match op.body.as_ref().map(|body| body.encoding) {
+        Some(ir::BodyEncoding::UrlEncoded) => "form",
+        Some(ir::BodyEncoding::Multipart) => "multipart",
+        Some(ir::BodyEncoding::Json) | None => "json",
+    };
+
+    // The encodings are left out when they are the default, unless an
+    // argument after them has to follow.
+    let response_encoding = response_encoding(op);
+    let validates = declarations.response_schema(options).is_some();
+    let trailing = match (validates, encoding, response_encoding) {
+        (true, _, _) => format!(", \"{encoding}\", \"{response_encoding}\", {response}"),
+        (false, "json", "json") => String::new(),
+        (false, _, "json") => format!(", \"{encoding}\""),
+        (false, _, _) => format!(", \"{encoding}\", \"{response_encoding}\""),
+    };
+
+    // Operations with parameters have always taken a body, typed `never` when
+    // the spec doesn't declare one.
+    if op.body.is_none() && op.parameters.is_empty() {
+        lines.push(format!(
+            r#"export async function {function}(props: {params}, init?: RequestInit) {{
+    return mutator<never, {response}, {error}>("{method}", "{key}", {props}, null, init{trailing});
+}}
+"#
+        ));
+        return;
+    }
+
+    lines.push(declarations.declare_body(&body_name, op.body.as_ref(), options));
+
     lines.push(format!(
-        r#"export async function get_{fn_name}(props: {tmp_key}) {{
-    return fetcher<{fn_name}_response, {fn_name}_error>("{key}", props);
+        r#"export async function {function}(props: {params}, body: {body_name}, init?: RequestInit) {{
+    return mutator<{body_name}, {response}, {error}>("{method}", "{key}", {props}, body, init{trailing});
 }}
 "#
     ));
 }
 
-fn generate_mutator(key: &String, method: &String, op: parser::Operation, lines: &mut Vec<String>) {
-    let _2xx_responses = op
-        .responses
-        .iter()
-        .filter(|(key, _)| key.starts_with("2"))
-        .collect::<Vec<_>>();
-    let non_2xx_responses = op
-        .responses
+/// `ResponseEncoding` the runtime reads successful responses of `op` with.
+fn response_encoding(op: &ir::Operation) -> &'static str {
+    match op.response_encoding() {
+        ir::ResponseEncoding::Json => "json",
+        ir::ResponseEncoding::Text => "text",
+        ir::ResponseEncoding::Binary => "blob",
+    }
+}
+
+pub fn parse_definition(ty: &ir::Type, options: &Options) -> String {
+    parse_type(ty, options, "{}")
+}
+
+pub fn parse_params(
+    op: &ir::Operation,
+    location: ir::ParameterLocation,
+    options: &Options,
+) -> String {
+    op.parameters_in(location)
+        .map(|param| {
+            let type_str = parse_property_type(&param.ty, options);
+
+            let type_str = match param.required {
+                true => type_str,
+                false => format!("{} | undefined", type_str),
+            };
+
+            format!(
+                "{}: {}",
+                sanitizer::property_name(&param.name, options),
+                with_null(type_str, param.nullable)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Serialization of array and object parameters, keyed by parameter name.
+pub fn parse_formats(op: &ir::Operation) -> Vec<(String, &'static str)> {
+    op.parameters
         .iter()
-        .filter(|(key, _)| key.starts_with("4") || key.starts_with("5"))
-        .collect::<Vec<_>>();
+        .filter_map(|param| Some((param.name.clone(), param.serialization?.as_str())))
+        .collect()
+}
 
-    let query_type = op.parse_query();
-    let path_type = op.parse_path();
+pub fn parse_body(body: &ir::Body, options: &Options) -> String {
+    parse_type(&body.ty, options, "unknown")
+}
 
-    let tmp_key = sanitizer::create_input_type_name_from_path(key, Some(&method.to_lowercase()));
-    let fn_name = generate_fn_name(method.clone(), key.to_string());
+pub fn parse_response(response: &ir::Response, options: &Options) -> String {
+    match (&response.ty, response.encoding) {
+        (Some(ty), ir::ResponseEncoding::Json) => {
+            format!("Promise<{}>", parse_type(ty, options, "unknown"))
+        }
+        (Some(_), ir::ResponseEncoding::Text) => "Promise<string>".to_string(),
+        (Some(_), ir::ResponseEncoding::Binary) => "Promise<Blob>".to_string(),
+        (None, _) => {
+            warn!("No schema found for response");
+            "Promise<unknown>".to_string()
+        }
+    }
+}
 
-    lines.push(format!("\n\n// Description - {}\n", op.description));
-    lines.push(format!(
-        "type {tmp_key} = {{ query: {{{query_type}}}, path: {{{path_type}}} }};\n"
-    ));
+fn parse_property_type(ty: &ir::Type, options: &Options) -> String {
+    parse_type(ty, options, "never")
+}
 
-    lines.push(format!(
-        "type {fn_name}_response = {};\n",
-        _2xx_responses
-            .iter()
-            .map(|(_, value)| value.parse_response())
-            .fold(HashSet::new(), |mut acc, x| {
-                acc.insert(x);
-                acc
-            })
+/// Renders any type, however deeply nested. `untyped` is used for schemas
+/// that say nothing about their shape.
+fn parse_type(ty: &ir::Type, options: &Options, untyped: &str) -> String {
+    match ty {
+        ir::Type::Any => untyped.to_string(),
+        ir::Type::Never => "never".to_string(),
+        ir::Type::Null => "null".to_string(),
+        ir::Type::Boolean => "boolean".to_string(),
+        ir::Type::Integer | ir::Type::Number => "number".to_string(),
+        ir::Type::String => "string".to_string(),
+        ir::Type::File => "Blob".to_string(),
+        ir::Type::Literal(value) => literal_type(value),
+        ir::Type::Enum(values) => values
             .iter()
-            .map(|val| val.to_string())
+            .map(literal_type)
             .collect::<Vec<_>>()
-            .join(" | ")
-    ));
-    lines.push(format!(
-        "type {fn_name}_error = {};\n",
-        if non_2xx_responses.is_empty() {
-            "never".to_string()
-        } else {
-            non_2xx_responses
+            .join(" | "),
+        ir::Type::Array(items) => array_of(parse_type(items, options, "unknown")),
+        ir::Type::Tuple { items, rest } => {
+            let mut members: Vec<String> = items
                 .iter()
-                .map(|(_, value)| value.parse_response())
-                .fold(HashSet::new(), |mut acc, x| {
-                    acc.insert(x);
-                    acc
-                })
-                .iter()
-                .map(|val| val.to_string())
-                .collect::<Vec<_>>()
-                .join(" | ")
+                .map(|item| parse_property_type(item, options))
+                .collect();
+
+            if let Some(rest) = rest {
+                members.push(format!(
+                    "...{}",
+                    array_of(parse_property_type(rest, options))
+                ));
+            }
+
+            format!("[{}]", members.join(", "))
         }
-    ));
+        ir::Type::Object(object) => parse_object_type(object, options),
+        ir::Type::Ref(name) => sanitizer::type_name(name, options),
+        ir::Type::Union(members) => members
+            .iter()
+            .map(|member| parse_property_type(member, options))
+            .collect::<Vec<_>>()
+            .join(" | "),
+        ir::Type::Intersection(members) => members
+            .iter()
+            .map(|member| wrap_union(parse_property_type(member, options)))
+            .collect::<Vec<_>>()
+            .join(" & "),
+        ir::Type::Variant {
+            property,
+            value,
+            ty,
+        } => format!(
+            "{} & {{{}:{};}}",
+            parse_property_type(ty, options),
+            sanitizer::property_name(property, options),
+            literal_type(&serde_json::Value::String(value.clone()))
+        ),
+        ir::Type::Nullable(inner) => with_null(parse_type(inner, options, untyped), true),
+    }
+}
 
-    match op.parameters {
-        Some(parameters) => {
-            let body_type = parameters
-                .iter()
-                .find(|x| match x.in_field {
-                    parser::OperationParameterField::Body => true,
-                    _ => false,
-                })
-                .map(|x| x.parse_body())
-                .unwrap_or("never".to_string());
+/// Declared properties and additional properties of an object. Maps become
+/// `Record<string, T>`, combined with the declared properties through an
+/// intersection so both stay typed.
+fn parse_object_type(object: &ir::ObjectType, options: &Options) -> String {
+    let record = object
+        .additional
+        .as_ref()
+        .map(|ty| format!("Record<string, {}>", parse_type(ty, options, "unknown")));
 
-            lines.push(format!("type {fn_name}_body= {body_type};\n",));
+    match (object.properties.is_empty(), record) {
+        (true, Some(record)) => record,
+        (false, Some(record)) => format!(
+            "{} & {}",
+            parse_properties(&object.properties, options),
+            record
+        ),
+        (_, None) => parse_properties(&object.properties, options),
+    }
+}
 
-            lines.push(format!(
-                r#"export async function {fn_name}(props: {tmp_key}, body: {fn_name}_body, init?: RequestInit) {{
-    return mutator<{fn_name}_body, {fn_name}_response, {fn_name}_error>("{method}", "{key}", props, body, init);
-}}
-"#
-            ));
+fn parse_properties(properties: &[ir::Property], options: &Options) -> String {
+    let tokens: Vec<String> = ordered_properties(properties, options)
+        .into_iter()
+        .map(|prop| {
+            let name = sanitizer::property_name(&prop.name, options);
+            let type_str = parse_property_type(&prop.ty, options);
+
+            match (prop.required, options.exact_optional_property_types) {
+                (true, _) => format!("{}:{};", name, type_str),
+                (false, false) => format!("{}?:{};", name, type_str),
+                (false, true) => format!("{}?:{} | undefined;", name, type_str),
+            }
+        })
+        .collect();
+
+    format!("{{{}}}", tokens.concat())
+}
+
+fn literal_type(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(v) => sanitizer::string_literal(v),
+        serde_json::Value::Number(_) | serde_json::Value::Bool(_) | serde_json::Value::Null => {
+            value.to_string()
         }
-        None => {
-            lines.push(format!(
-                r#"export async function {fn_name}(props: {tmp_key}, init?: RequestInit) {{
-    return mutator<never, {fn_name}_response, {fn_name}_error>("{method}", "{key}", props, null, init);
-}}
-"#
-            ));
+        _ => {
+            warn!("Unsupported literal value: {}", value);
+            "unknown".to_string()
         }
     }
 }
+
+fn with_null(type_str: String, nullable: bool) -> String {
+    if !nullable || type_str.split(" | ").any(|member| member == "null") {
+        return type_str;
+    }
+
+    format!("{} | null", type_str)
+}
+
+/// Wraps unions in parentheses so they keep their meaning inside an
+/// intersection.
+fn wrap_union(type_str: String) -> String {
+    match type_str.contains(" | ") {
+        true => format!("({})", type_str),
+        false => type_str,
+    }
+}
+
+/// Element type of an array, parenthesized when it would otherwise bind
+/// looser than `[]`.
+fn array_of(type_str: String) -> String {
+    match type_str.contains(" | ") || type_str.contains(" & ") {
+        true => format!("({})[]", type_str),
+        false => format!("{}[]", type_str),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::naming::NamingStyle;
+
+    #[test]
+    fn comment_multiline_descriptions() {
+        assert_eq!(
+            description_comment("Lists pets.\n\nPaginated.\n"),
+            "\n\n// Description - Lists pets.\n//\n// Paginated.\n"
+        );
+        assert_eq!(description_comment("Ping"), "\n\n// Description - Ping\n");
+    }
+
+    #[test]
+    fn rename_types_shadowing_globals() {
+        let api = ir::Api {
+            types: indexmap::IndexMap::from([("Error".to_string(), ir::Type::String)]),
+            ..Default::default()
+        };
+
+        let lines = generate_file_lines(api.clone(), &Options::default(), &TypeScript).concat();
+        assert!(lines.contains("export type Error_2 = string;\n"));
+
+        let lines = generate_file_lines(api, &Options::default(), &crate::zod::Zod).concat();
+        assert!(lines.contains("export const Error_2 = z.string();\n"));
+    }
+
+    #[test]
+    fn shape_only_declared_properties() {
+        let property = |name: &str, ty| ir::Property {
+            name: name.to_string(),
+            description: None,
+            required: false,
+            ty,
+        };
+        let thing = ir::Type::Object(ir::ObjectType {
+            properties: vec![
+                property("thing_name", ir::Type::String),
+                property(
+                    "labels",
+                    ir::Type::Object(ir::ObjectType {
+                        properties: vec![],
+                        additional: Some(Box::new(ir::Type::String)),
+                    }),
+                ),
+                property(
+                    "children",
+                    ir::Type::Array(Box::new(ir::Type::Ref("Thing".to_string()))),
+                ),
+            ],
+            additional: None,
+        });
+        let api = ir::Api {
+            types: indexmap::IndexMap::from([
+                ("Thing".to_string(), thing),
+                ("Tag".to_string(), ir::Type::String),
+            ]),
+            ..Default::default()
+        };
+
+        assert_eq!(generate_shapes(&api, &Options::default()), None);
+
+        let options = Options {
+            property_case: NamingStyle::Camel,
+            ..Default::default()
+        };
+        let (shapes, _) = generate_shapes(&api, &options).unwrap();
+        assert_eq!(
+            shapes,
+            r#"{ "Thing": { props: { "thingName": ["thing_name"], "children": ["children", { items: "Thing" }] } } }"#
+        );
+    }
+
+    #[test]
+    fn rename_params_of_each_operation() {
+        let param = |name: &str| ir::Parameter {
+            name: name.to_string(),
+            location: ir::ParameterLocation::Query,
+            required: true,
+            nullable: false,
+            ty: ir::Type::String,
+            serialization: None,
+        };
+        let operation = |path: &str, name: &str| ir::Operation {
+            method: ir::Method::Get,
+            path: path.to_string(),
+            operation_id: None,
+            description: String::new(),
+            parameters: vec![param(name)],
+            body: None,
+            responses: vec![],
+        };
+        let mut api = ir::Api {
+            operations: vec![operation("/a", "user_id"), operation("/b", "userId")],
+            ..Default::default()
+        };
+        let options = Options {
+            property_case: NamingStyle::Camel,
+            ..Default::default()
+        };
+
+        let (_, operation_shapes) = generate_shapes(&api, &options).unwrap();
+        assert_eq!(
+            operation_shapes,
+            r#"{ "GET /a": { params: { props: { query: ["query", { props: { "userId": ["user_id"] } }] } } } }"#
+        );
+        assert!(check_renames(&api, &options).is_ok());
+
+        api.operations[1].parameters.push(param("user_id"));
+        assert_eq!(
+            check_renames(&api, &options).unwrap_err().to_string(),
+            "userId and user_id of GET /b are both named userId by property_case"
+        );
+    }
+
+    #[test]
+    fn leave_out_renaming_runtime_without_renames() {
+        let lines = generate_file_lines(ir::Api::default(), &Options::default(), &TypeScript);
+        let contents = lines.concat();
+
+        assert!(!contents.contains("renameKeys"));
+        assert!(!contents.contains("operationShapes"));
+        assert!(contents.contains("body: encodeBody(body, encoding, headers),"));
+        assert!(contents.contains("const bodyData = await readBody(res, responseEncoding);"));
+    }
+
+    #[test]
+    fn encode_object_fields_as_json() {
+        let lines = generate_file_lines(ir::Api::default(), &Options::default(), &TypeScript);
+        let contents = lines.concat();
+
+        assert!(contents.contains("form.append(key, fieldValue(value))"));
+        assert!(contents
+            .contains("form.append(key, value instanceof Blob ? value : fieldValue(value))"));
+        assert!(contents.contains(
+            r#"return value !== null && typeof value === "object" ? JSON.stringify(value) : String(value);"#
+        ));
+    }
+
+    #[test]
+    fn read_responses_by_media_type() {
+        let operation = |method, path: &str, encoding| ir::Operation {
+            method,
+            path: path.to_string(),
+            operation_id: None,
+            description: String::new(),
+            parameters: vec![],
+            body: None,
+            responses: vec![ir::Response {
+                status: "200".to_string(),
+                ty: Some(ir::Type::String),
+                encoding,
+            }],
+        };
+        let api = ir::Api {
+            operations: vec![
+                operation(ir::Method::Get, "/report", ir::ResponseEncoding::Text),
+                operation(ir::Method::Post, "/exec", ir::ResponseEncoding::Binary),
+            ],
+            ..Default::default()
+        };
+
+        let contents = generate_file_lines(api, &Options::default(), &TypeScript).concat();
+
+        assert!(contents.contains("type get_report_response = Promise<string>;"));
+        assert!(contents.contains(r#"fetcher<get_report_response, get_report_error>("/report", props, undefined, "text");"#));
+        assert!(contents.contains("type post_exec_response = Promise<Blob>;"));
+        assert!(contents.contains(r#"("POST", "/exec", props, null, init, "json", "blob");"#));
+    }
+}
@@ -0,0 +1,376 @@
+use anyhow::Result;
+use log::warn;
+use std::path::Path;
+
+use crate::generator::{ordered_properties, Generator, OutputFile};
+use crate::ir;
+use crate::options::Options;
+use crate::sanitizer;
+use crate::template::{self, Declarations};
+
+/// The TypeScript client with every type declared as a Zod schema, the types
+/// are inferred from them.
+pub struct Zod;
+
+impl Generator for Zod {
+    fn generate(&self, api: ir::Api, options: &Options, path: &Path) -> Result<Vec<OutputFile>> {
+        template::generate_file(api, options, path, self)
+    }
+}
+
+impl Declarations for Zod {
+    fn header(&self) -> &'static str {
+        "import { z } from \"zod\";\n"
+    }
+
+    fn reserved(&self) -> &'static [&'static str] {
+        &["z"]
+    }
+
+    fn declares_schemas(&self) -> bool {
+        true
+    }
+
+    fn response_schema(&self, options: &Options) -> Option<&'static str> {
+        options.validate_responses.then_some("z.ZodType<TResult>")
+    }
+
+    fn declare_definition(
+        &self,
+        name: &str,
+        ty: &ir::Type,
+        recursive: bool,
+        options: &Options,
+    ) -> String {
+        match recursive {
+            true => declare_recursive(
+                name,
+                &template::parse_definition(ty, options),
+                &parse_type(ty, options),
+            ),
+            false => declare(name, &parse_type(ty, options)),
+        }
+    }
+
+    fn declare_union(&self, name: &str, members: Vec<&ir::Type>, options: &Options) -> String {
+        let schemas = members.into_iter().map(|ty| parse_type(ty, options));
+
+        declare(name, &union(schemas.collect()))
+    }
+
+    fn declare_params(&self, name: &str, op: &ir::Operation, options: &Options) -> String {
+        let mut fields = vec![
+            format!(
+                "query: {}",
+                object(&parse_params(op, ir::ParameterLocation::Query, options))
+            ),
+            format!(
+                "path: {}",
+                object(&parse_params(op, ir::ParameterLocation::Path, options))
+            ),
+        ];
+
+        [
+            ("header", ir::ParameterLocation::Header),
+            ("cookie", ir::ParameterLocation::Cookie),
+        ]
+        .into_iter()
+        .for_each(|(field, location)| {
+            let params = parse_params(op, location, options);
+            if !params.is_empty() {
+                fields.push(format!("{field}: {}", object(&params)));
+            }
+        });
+
+        declare(name, &object(&fields))
+    }
+
+    fn declare_responses(
+        &self,
+        op: &ir::Operation,
+        response: &str,
+        error: &str,
+        options: &Options,
+    ) -> Vec<String> {
+        let schemas = |classes| {
+            op.responses_in(classes)
+                .map(|response| parse_response(response, options))
+                .collect::<Vec<_>>()
+        };
+        let success = match schemas(&["2"]) {
+            success if success.is_empty() => "z.unknown()".to_string(),
+            success => union(success),
+        };
+
+        vec![
+            declare(response, &success),
+            declare(error, &union(schemas(&["4", "5"]))),
+        ]
+    }
+
+    fn declare_body(&self, name: &str, body: Option<&ir::Body>, options: &Options) -> String {
+        let schema = match body {
+            Some(body) => parse_type(&body.ty, options),
+            None => "z.never()".to_string(),
+        };
+
+        declare(name, &schema)
+    }
+}
+
+/// Exports a schema and the type inferred from it under the same name.
+fn declare(name: &str, schema: &str) -> String {
+    format!("export const {name} = {schema};\nexport type {name} = z.infer<typeof {name}>;\n")
+}
+
+/// Zod can't infer recursive schemas, their type is spelled out and the
+/// schema annotated with it.
+fn declare_recursive(name: &str, type_str: &str, schema: &str) -> String {
+    format!(
+        "export type {name} = {type_str};\nexport const {name}: z.ZodType<{name}> = {schema};\n"
+    )
+}
+
+/// Schema accepting any of `members`, skipping duplicates.
+fn union(members: Vec<String>) -> String {
+    let members = members.into_iter().fold(Vec::new(), |mut acc, x| {
+        if !acc.contains(&x) {
+            acc.push(x);
+        }
+        acc
+    });
+
+    match members.len() {
+        0 => "z.never()".to_string(),
+        1 => members.into_iter().next().unwrap(),
+        _ => format!("z.union([{}])", members.join(", ")),
+    }
+}
+
+fn object(fields: &[String]) -> String {
+    match fields.is_empty() {
+        true => "z.object({})".to_string(),
+        false => format!("z.object({{ {} }})", fields.join(", ")),
+    }
+}
+
+/// Fields of the schema of the parameters in `location`.
+fn parse_params(
+    op: &ir::Operation,
+    location: ir::ParameterLocation,
+    options: &Options,
+) -> Vec<String> {
+    op.parameters_in(location)
+        .map(|param| {
+            let mut schema = parse_type(&param.ty, options);
+
+            if param.nullable && !matches!(param.ty, ir::Type::Null | ir::Type::Nullable(_)) {
+                schema.push_str(".nullable()");
+            }
+            if !param.required {
+                schema.push_str(".optional()");
+            }
+
+            format!(
+                "{}: {}",
+                sanitizer::property_name(&param.name, options),
+                schema
+            )
+        })
+        .collect()
+}
+
+fn parse_response(response: &ir::Response, options: &Options) -> String {
+    match (&response.ty, response.encoding) {
+        (Some(ty), ir::ResponseEncoding::Json) => parse_type(ty, options),
+        (Some(_), ir::ResponseEncoding::Text) => "z.string()".to_string(),
+        (Some(_), ir::ResponseEncoding::Binary) => "z.instanceof(Blob)".to_string(),
+        (None, _) => {
+            warn!("No schema found for response");
+            "z.unknown()".to_string()
+        }
+    }
+}
+
+/// Schema validating any type. Named types are referenced lazily so they can
+/// be declared in any order.
+fn parse_type(ty: &ir::Type, options: &Options) -> String {
+    match ty {
+        ir::Type::Any => "z.unknown()".to_string(),
+        ir::Type::Never => "z.never()".to_string(),
+        ir::Type::Null => "z.null()".to_string(),
+        ir::Type::Boolean => "z.boolean()".to_string(),
+        ir::Type::Integer => "z.number().int()".to_string(),
+        ir::Type::Number => "z.number()".to_string(),
+        ir::Type::String => "z.string()".to_string(),
+        ir::Type::File => "z.instanceof(Blob)".to_string(),
+        ir::Type::Literal(value) => literal(value),
+        ir::Type::Enum(values) => match values.iter().all(serde_json::Value::is_string) {
+            true if values.len() > 1 => format!(
+                "z.enum([{}])",
+                values
+                    .iter()
+                    .filter_map(serde_json::Value::as_str)
+                    .map(sanitizer::string_literal)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            _ => union(values.iter().map(literal).collect()),
+        },
+        ir::Type::Array(items) => format!("z.array({})", parse_type(items, options)),
+        ir::Type::Tuple { items, rest } => {
+            let tuple = format!(
+                "z.tuple([{}])",
+                items
+                    .iter()
+                    .map(|item| parse_type(item, options))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+
+            match rest {
+                Some(rest) => format!("{tuple}.rest({})", parse_type(rest, options)),
+                None => tuple,
+            }
+        }
+        ir::Type::Object(object) => parse_object(object, options),
+        ir::Type::Ref(name) => format!("z.lazy(() => {})", sanitizer::type_name(name, options)),
+        ir::Type::Union(members) => union(
+            members
+                .iter()
+                .map(|member| parse_type(member, options))
+                .collect(),
+        ),
+        ir::Type::Intersection(members) => members
+            .iter()
+            .map(|member| parse_type(member, options))
+            .reduce(|acc, member| format!("z.intersection({acc}, {member})"))
+            .unwrap_or_else(|| "z.unknown()".to_string()),
+        ir::Type::Variant {
+            property,
+            value,
+            ty,
+        } => format!(
+            "z.intersection({}, {})",
+            parse_type(ty, options),
+            object(&[format!(
+                "{}: z.literal({})",
+                sanitizer::property_name(property, options),
+                sanitizer::string_literal(value)
+            )])
+        ),
+        ir::Type::Nullable(inner) => format!("{}.nullable()", parse_type(inner, options)),
+    }
+}
+
+/// Declared properties become a `z.object`, undeclared ones a record or its
+/// catchall. Objects closed with `additionalProperties: false` are strict.
+fn parse_object(object_type: &ir::ObjectType, options: &Options) -> String {
+    let shape = object(
+        &ordered_properties(&object_type.properties, options)
+            .into_iter()
+            .map(|prop| {
+                format!(
+                    "{}: {}{}",
+                    sanitizer::property_name(&prop.name, options),
+                    parse_type(&prop.ty, options),
+                    if prop.required { "" } else { ".optional()" }
+                )
+            })
+            .collect::<Vec<_>>(),
+    );
+
+    match (
+        object_type.properties.is_empty(),
+        object_type.additional.as_deref(),
+    ) {
+        (true, Some(ty)) => format!("z.record(z.string(), {})", parse_type(ty, options)),
+        (false, Some(ir::Type::Never)) => format!("{shape}.strict()"),
+        (false, Some(ty)) => format!("{shape}.catchall({})", parse_type(ty, options)),
+        (_, None) => shape,
+    }
+}
+
+fn literal(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => "z.null()".to_string(),
+        serde_json::Value::String(v) => format!("z.literal({})", sanitizer::string_literal(v)),
+        serde_json::Value::Number(_) | serde_json::Value::Bool(_) => {
+            format!("z.literal({value})")
+        }
+        _ => {
+            warn!("Unsupported literal value: {}", value);
+            "z.unknown()".to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_zod_schemas() {
+        let options = Options::default();
+        let pet = ir::Type::Object(ir::ObjectType {
+            properties: vec![
+                ir::Property {
+                    name: "name".to_string(),
+                    description: None,
+                    required: true,
+                    ty: ir::Type::String,
+                },
+                ir::Property {
+                    name: "age".to_string(),
+                    description: None,
+                    required: false,
+                    ty: ir::Type::Integer.nullable(),
+                },
+                ir::Property {
+                    name: "owner".to_string(),
+                    description: None,
+                    required: false,
+                    ty: ir::Type::Ref("Owner".to_string()),
+                },
+            ],
+            additional: Some(Box::new(ir::Type::Never)),
+        });
+
+        assert_eq!(
+            parse_type(&pet, &options),
+            "z.object({ age: z.number().int().nullable().optional(), name: z.string(), owner: z.lazy(() => Owner).optional() }).strict()"
+        );
+        assert_eq!(
+            parse_type(&ir::Type::Enum(vec!["a".into(), "b".into()]), &options),
+            "z.enum(['a', 'b'])"
+        );
+        assert_eq!(
+            parse_type(
+                &ir::Type::Enum(vec!["a".into(), 1.into(), serde_json::Value::Null]),
+                &options
+            ),
+            "z.union([z.literal('a'), z.literal(1), z.null()])"
+        );
+        assert_eq!(
+            parse_type(
+                &ir::Type::Object(ir::ObjectType {
+                    properties: Vec::new(),
+                    additional: Some(Box::new(ir::Type::Any)),
+                }),
+                &options
+            ),
+            "z.record(z.string(), z.unknown())"
+        );
+        assert_eq!(
+            parse_type(
+                &ir::Type::Union(vec![ir::Type::Variant {
+                    property: "kind".to_string(),
+                    value: "Cat".to_string(),
+                    ty: Box::new(ir::Type::Ref("Cat".to_string())),
+                }]),
+                &options
+            ),
+            "z.intersection(z.lazy(() => Cat), z.object({ kind: z.literal('Cat') }))"
+        );
+    }
+}